@@ -0,0 +1,15 @@
+#!/usr/bin/env solar
+use @std.types.get
+use @std.os.env_var
+use @std.io.println
+
+# run with
+#   solar run samples/5-args.sol -- Peter
+fun main(args: Array) =
+    let name = get args 0,
+        shell = env_var "SHELL"
+    in
+        println ("Hello " ++ name ++ ", running in " ++ shell)
+
+fun concat(a: String, b: String) -> String =
+    buildin_str_concat a b
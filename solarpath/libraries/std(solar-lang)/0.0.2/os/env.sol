@@ -0,0 +1,7 @@
+
+# Value of the environment variable
+# or an empty string, if it is not set
+pub fun env_var(name: String) -> String = buildin_env_var name
+
+# Whether the environment variable is set
+pub fun has_env_var(name: String) -> Bool = buildin_has_env_var name
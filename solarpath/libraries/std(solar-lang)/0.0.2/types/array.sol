@@ -0,0 +1,13 @@
+
+buildin_type Array
+
+pub fun concatArrays(a: Array, b: Array) -> Array =
+  buildin_arr_concat a b
+
+# Number of elements inside the array
+pub fun length(a: Array) -> Int =
+  buildin_arr_len a
+
+# Element at position index, starting at 0
+pub fun get(a: Array, index: Int) =
+  buildin_arr_get a index
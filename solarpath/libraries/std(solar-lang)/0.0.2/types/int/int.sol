@@ -0,0 +1,16 @@
+
+buildin_type Bool
+buildin_type Int8
+buildin_type Int16
+buildin_type Int32
+# the standard Int type has 64 Bits
+buildin_type Int
+buildin_type Uint8
+buildin_type Uint16
+buildin_type Uint32
+# the standard Uint type has 64 Bits
+buildin_type Uint
+buildin_type Float32
+
+# the standard Float type has 64 Bits
+buildin_type Float
\ No newline at end of file
@@ -0,0 +1,5 @@
+
+buildin_type String
+
+pub fun concat(a: String, b: String) -> String =
+  buildin_str_concat a b
@@ -0,0 +1,2 @@
+
+pub fun &(a) = buildin_identity a
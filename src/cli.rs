@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::config::{SOLAR_LOCKED, SOLAR_PATH};
+
+/// Command line interface of the solar binary.
+#[derive(Debug, Parser)]
+#[command(
+    name = "solar",
+    version,
+    about = "Compiler and interpreter for the solar language"
+)]
+pub struct Cli {
+    /// Directory containing installed libraries.
+    /// Overrides the SOLAR_PATH environment variable.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub solar_path: Option<String>,
+
+    /// Require dependencies to match the solar.lock exactly,
+    /// instead of updating it
+    #[arg(long, global = true)]
+    pub locked: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Compile and run the main function of a project
+    Run {
+        #[command(flatten)]
+        project: ProjectArgs,
+
+        /// Function to run instead of main, e.g. `util.greet`.
+        /// Arguments are parsed according to the declared parameter types.
+        #[arg(long, value_name = "PATH")]
+        entry: Option<String>,
+
+        #[command(flatten)]
+        output: ResultOutput,
+
+        /// Arguments passed on to the main function of the program
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Compile a project without running it, only reporting errors
+    Check(ProjectArgs),
+
+    /// Compile and run the tests of a project,
+    /// that is every function without arguments named `test_*`
+    Test(ProjectArgs),
+
+    /// Compile a project and write the resulting MIR
+    Build {
+        #[command(flatten)]
+        project: ProjectArgs,
+
+        /// File to write the MIR to. Defaults to stdout.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+
+        /// Build twice and fail, if the resulting MIR differs
+        #[arg(long)]
+        verify_determinism: bool,
+    },
+
+    /// Create a new project in a new directory
+    New {
+        /// Directory to create the project in
+        dir: String,
+
+        #[command(flatten)]
+        template: TemplateArgs,
+    },
+
+    /// Create a new project in an existing directory
+    Init {
+        /// Directory to create the project in
+        #[arg(default_value = ".")]
+        dir: String,
+
+        #[command(flatten)]
+        template: TemplateArgs,
+    },
+
+    /// Inspect the dependencies of a project
+    Deps {
+        #[command(subcommand)]
+        command: DepsCommand,
+    },
+
+    /// Print the resolved configuration of a project
+    Config {
+        #[command(flatten)]
+        project: ProjectArgs,
+
+        /// Also show which layer each value was taken from
+        #[arg(long)]
+        show: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DepsCommand {
+    /// Print the resolved dependencies as tree,
+    /// along with the basepath, location and import alias of every library
+    Tree {
+        #[command(flatten)]
+        project: ProjectArgs,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Text)]
+        format: GraphFormat,
+    },
+}
+
+/// Output format of the dependency graph
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// Human readable tree
+    Text,
+    /// Graphviz DOT language
+    Dot,
+    Json,
+}
+
+impl Cli {
+    /// Parses the command line arguments.
+    /// `solar <file.sol> ...` is treated as `solar run <file.sol> ...`,
+    /// so that solar files can be executed directly via `#!/usr/bin/env solar`.
+    /// All arguments after the file are passed on to the program.
+    pub fn from_args() -> Self {
+        let mut args: Vec<String> = std::env::args().collect();
+
+        if args
+            .get(1)
+            .is_some_and(|arg| arg.ends_with(".sol") && Path::new(arg).is_file())
+        {
+            args.insert(1, "run".to_string());
+
+            // the shebang can't supply `--`, which separates the program arguments.
+            if args.get(3).map(String::as_str) != Some("--") {
+                args.insert(3, "--".to_string());
+            }
+        }
+
+        Cli::parse_from(args)
+    }
+
+    /// Configuration values supplied via command line arguments.
+    /// These take precedence over all other configuration layers.
+    pub fn config_overrides(&self) -> HashMap<String, String> {
+        let mut overrides = HashMap::new();
+
+        if let Some(path) = &self.solar_path {
+            overrides.insert(SOLAR_PATH.to_string(), path.clone());
+        }
+
+        if self.locked {
+            overrides.insert(SOLAR_LOCKED.to_string(), "true".to_string());
+        }
+
+        overrides
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ProjectArgs {
+    /// Root directory of the project, containing the solar.yaml.
+    /// May also be a single .sol file, which is run as a script.
+    #[arg(default_value = ".")]
+    pub dir: String,
+}
+
+/// Policy for printing the value returned by the entry function.
+/// By default, integers become the exit code of the process
+/// and all other values (except Void) are printed to stdout.
+#[derive(Debug, Args)]
+pub struct ResultOutput {
+    /// Always print the returned value, including integers
+    #[arg(long, conflicts_with = "quiet")]
+    pub print_result: bool,
+
+    /// Never print the returned value
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateArgs {
+    /// Create a library instead of an application
+    #[arg(long)]
+    pub lib: bool,
+
+    /// Name of the project. Defaults to the name of the directory
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Publisher of the project
+    #[arg(long)]
+    pub publisher: Option<String>,
+}
@@ -1,9 +1,10 @@
+use std::collections::HashSet;
 use std::ops::Deref;
 
 use hotel::HotelMap;
 
 use crate::{
-    id::{TypeId, SSID},
+    id::{SymbolId, TypeId, SSID},
     mir::StaticExpression,
 };
 
@@ -24,6 +25,8 @@ pub enum FunctionInfo {
 #[derive(Default)]
 pub struct FunctionStore {
     functions: HotelMap<SSID, FunctionInfo>,
+    /// Functions, that have been compiled for any argument types
+    symbols: HashSet<SymbolId>,
 }
 
 impl FunctionStore {
@@ -33,9 +36,15 @@ impl FunctionStore {
 
     /// Reserve a slot in the hotel map
     pub fn reserve(&mut self, key: SSID) -> usize {
+        self.symbols.insert(key.0.clone());
         self.functions.insert(key, FunctionInfo::Partial)
     }
 
+    /// Whether the function has been compiled, for any argument types.
+    pub fn contains_symbol(&self, symbol_id: &SymbolId) -> bool {
+        self.symbols.contains(symbol_id)
+    }
+
     pub fn update_complete_function(
         &mut self,
         index: usize,
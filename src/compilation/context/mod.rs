@@ -3,10 +3,12 @@ pub use function_store::*;
 
 use super::CompilationError;
 use crate::{
-    id::{FunctionId, IdItem, IdModule, Symbol, SymbolId, TypeId, SSID},
+    id::{FunctionId, IdItem, Symbol, SymbolId, TypeId, SSID},
     mir::Value,
     mir::{CustomInstructionCode, Instruction, StaticExpression},
-    project::{FileInfo, FindError, GlobalModules, Module, ProjectInfo, SymbolResolver},
+    project::{
+        FileId, FileInfo, FindError, GlobalModules, Module, ProjectInfo, SymbolResolver, UsedImport,
+    },
     types::{
         buildin::{link_buildin_types, BuildinTypeId},
         Type,
@@ -19,6 +21,7 @@ use solar_parser::ast::{
     body::BodyItem,
     expr::{FullExpression, Literal},
 };
+use std::collections::HashSet;
 use std::sync::RwLock;
 
 /// Struct that gets created once globally
@@ -36,6 +39,10 @@ pub struct CompilerContext<'a> {
     pub types: RwLock<HotelMap<SSID, Type>>,
 
     pub functions: RwLock<FunctionStore>,
+
+    /// Imports, that have been consulted while resolving symbols.
+    /// Used to warn about unused imports after compilation.
+    pub used_imports: RwLock<HashSet<UsedImport>>,
 }
 
 impl<'a> CompilerContext<'a> {
@@ -54,6 +61,7 @@ impl<'a> CompilerContext<'a> {
             types,
             functions,
             buildin_types,
+            used_imports: Default::default(),
         }
     }
 
@@ -83,20 +91,82 @@ impl<'a> CompilerContext<'a> {
 
     /// Finds the main function of the current target project
     pub fn find_target_main(&'a self) -> Result<SymbolId, FindError> {
-        let path = util::target_id();
-        let module = self.module_info.get(&path).unwrap();
+        self.find_target_function(&["main".to_string()])
+    }
 
-        let mut candidates = module.find("main", &path)?;
+    /// Finds a function inside the current target project.
+    /// The path is relative to the project root, e.g. `["util", "greet"]`.
+    pub fn find_target_function(&'a self, path: &[String]) -> Result<SymbolId, FindError> {
+        let (symbol, module_path) = path
+            .split_last()
+            .ok_or_else(|| FindError::NotFound(String::new()))?;
+
+        let idmodule: IdPath = util::target_id()
+            .into_iter()
+            .chain(module_path.iter().cloned())
+            .collect();
+        let module = self.resolve_module(&idmodule)?;
+
+        // only functions can be called
+        let mut candidates = module
+            .find(symbol)?
+            .iter()
+            .filter(|(_, _, item)| matches!(item, IdItem::Func(_)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return Err(FindError::NotFound(symbol.to_string()));
+        }
 
         if candidates.len() != 1 {
             return Err(FindError::TooMany {
-                symbol: "main".to_string(),
-                module: path,
+                symbol: symbol.to_string(),
+                module: idmodule,
             });
         }
 
-        let f_main = candidates.pop().unwrap();
-        Ok(f_main)
+        let function = candidates.pop().unwrap();
+        Ok(function)
+    }
+
+    /// Finds all tests of the current target project,
+    /// that is every function without arguments, whose name starts with `test_`.
+    /// Returns the name of each test relative to the project root, e.g. `util.test_greet`,
+    /// sorted by name.
+    pub fn find_target_tests(&self) -> Vec<(String, SymbolId)> {
+        let target = util::target_id();
+        let mut tests = Vec::new();
+
+        for (idmodule, module) in &self.module_info {
+            if !idmodule.starts_with(&target) {
+                continue;
+            }
+
+            for (idfile, file) in module.files.iter().enumerate() {
+                for (iditem, item) in file.ast.items.iter().enumerate() {
+                    let BodyItem::Function(f) = item else {
+                        continue;
+                    };
+
+                    if !f.name.value.starts_with("test_") || !f.args.is_empty() {
+                        continue;
+                    }
+
+                    let name = idmodule[target.len()..]
+                        .iter()
+                        .map(String::as_str)
+                        .chain([f.name.value])
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    let symbol_id = (idmodule.clone(), idfile as u16, IdItem::Func(iditem as u16));
+                    tests.push((name, symbol_id));
+                }
+            }
+        }
+
+        tests.sort_by(|(a, _), (b, _)| a.cmp(b));
+        tests
     }
 
     /// Resolve module based on idpath
@@ -111,8 +181,10 @@ impl<'a> CompilerContext<'a> {
 #[derive(Clone)]
 struct Lookup<'a> {
     module: &'a Module<'a>,
-    idmodule: IdModule,
+    /// File the symbols are looked up from
+    file_id: FileId,
     imports: &'a SymbolResolver,
+    glob_imports: &'a [IdPath],
 }
 
 /// Evaluation related stuff.
@@ -128,8 +200,9 @@ impl<'a> CompilerContext<'a> {
 
         let lookup = Lookup {
             module,
-            idmodule: symbol_id.0.clone(),
+            file_id: fileinfo.file_id,
             imports: &fileinfo.imports,
+            glob_imports: &fileinfo.glob_imports,
         };
 
         match item {
@@ -517,14 +590,21 @@ impl<'a> CompilerContext<'a> {
     /// e.g. seek through all ASTs in module
     /// candidates.append_all(find_in_module(full_path))
     ///
+    /// 3.) if there are no candidates yet,
+    ///     see, if the element is from a wildcard import (use std.string..)
+    /// candidates.append_all(find_in_module(glob_module)) for every glob_module
+    ///
+    /// 4.) remove candidates of other projects, that aren't declared `pub`
+    ///
     /// return candidates
     fn resolve_symbol(
         &'a self,
         path: &[String],
         Lookup {
             module,
-            idmodule,
+            file_id,
             imports,
+            glob_imports,
         }: Lookup,
         _arg_types: &[TypeId],
         scope: &Scope,
@@ -552,15 +632,18 @@ impl<'a> CompilerContext<'a> {
             }
         }
 
+        // project, the symbol is looked up from
+        let project_id = module.project_id;
+
         let mut candidates: Vec<Symbol> = Vec::new();
         if let [name] = path {
             // if the path is only one element long,
             // we must also look up the local module.
             // that is ALL Asts within this module.
 
-            if let Ok(res) = module.find(name, &idmodule) {
+            if let Ok(res) = module.find(name) {
                 for symbolid in res {
-                    candidates.push(Symbol::Global(symbolid));
+                    candidates.push(Symbol::Global(symbolid.clone()));
                 }
             }
 
@@ -584,6 +667,8 @@ impl<'a> CompilerContext<'a> {
         let symbol = &path[0];
         if let Some(imports) = imports.get(symbol) {
             for import in imports {
+                self.mark_import_used(file_id, Some(symbol), import);
+
                 // TODO if path[1..].len() > 1, then imports should be length 1.
                 // because it means we are importing an entire module, and we shouldn't import multiple modules
                 // with the same name, I think.
@@ -605,18 +690,88 @@ impl<'a> CompilerContext<'a> {
                 };
 
                 // candidates from this module
-                let Ok(cs) = module.find(symbol, &idmodule) else {
+                let Ok(cs) = module.find(symbol) else {
                     continue;
                 };
 
                 for c in cs {
-                    candidates.push(Symbol::Global(c));
+                    candidates.push(Symbol::Global(c.clone()));
+                }
+            }
+        }
+
+        // 3.) see, if the element is from a wildcard import.
+        // These have the lowest priority, so that local definitions and explicit imports
+        // shadow them. Otherwise adding a function to an imported module
+        // might change, which function gets called.
+        if let [name] = path {
+            if candidates.is_empty() {
+                for idmodule in glob_imports {
+                    let Ok(module) = self.resolve_module(idmodule) else {
+                        continue;
+                    };
+
+                    let Ok(cs) = module.find(name) else {
+                        continue;
+                    };
+
+                    // wildcard imports only import public symbols.
+                    let cs = cs
+                        .iter()
+                        .filter(|c| module.is_visible_from(c, project_id))
+                        .collect::<Vec<_>>();
+                    if cs.is_empty() {
+                        continue;
+                    }
+
+                    self.mark_import_used(file_id, None, idmodule);
+                    for c in cs {
+                        candidates.push(Symbol::Global(c.clone()));
+                    }
                 }
             }
         }
 
+        // 4.) only keep candidates, that may be used from this module.
+        // If that removes every candidate, the symbol is private.
+        let mut private = None;
+        candidates.retain(|c| {
+            let Symbol::Global(symbol_id) = c else {
+                return true;
+            };
+
+            let module = self
+                .resolve_module(&symbol_id.0)
+                .expect("module of candidate to exist");
+            let visible = module.is_visible_from(symbol_id, project_id);
+            if !visible && private.is_none() {
+                private = Some(CompilationError::PrivateItem {
+                    name: path.join("."),
+                    declaration: module.declaration(symbol_id),
+                });
+            }
+
+            visible
+        });
+
+        if candidates.is_empty() {
+            if let Some(e) = private {
+                return Err(e);
+            }
+        }
+
         Ok(candidates)
     }
+
+    /// Remembers, that an import has been consulted,
+    /// so it isn't reported as unused.
+    fn mark_import_used(&self, file_id: FileId, symbol: Option<&String>, module: &IdPath) {
+        let used = (file_id, symbol.cloned(), module.clone());
+        self.used_imports
+            .write()
+            .expect("aquire writelock for used imports")
+            .insert(used);
+    }
 }
 
 fn compile_constant_value(
@@ -668,6 +823,10 @@ impl<'a> CompilerContext<'a> {
             "identity" => self.buildin_identity(args),
             "readline" => self.buildin_readline(args),
             "print" => self.buildin_print(args),
+            "arr_len" => self.buildin_arr_len(args),
+            "arr_get" => self.buildin_arr_get(args),
+            "env_var" => self.buildin_env_var(args),
+            "has_env_var" => self.buildin_has_env_var(args),
 
             _ => Err(CompilationError::WrongBuildin {
                 found: fname.to_string(),
@@ -770,4 +929,95 @@ impl<'a> CompilerContext<'a> {
             self.buildin_types.string as TypeId,
         ))
     }
+
+    /// Returns the type of the elements of an array type,
+    /// or None if the type is not an array.
+    fn array_element_type(&self, ty: TypeId) -> Option<TypeId> {
+        // TODO look up generic arguments of the type, once there are more kinds of arrays
+        if self.buildin_types.string_array.map(TypeId::from) == Some(ty) {
+            return Some(self.buildin_types.string as TypeId);
+        }
+
+        None
+    }
+
+    pub(crate) fn buildin_arr_len(
+        &self,
+        args: &[StaticExpression],
+    ) -> Result<(CustomInstructionCode, TypeId), CompilationError> {
+        // allowed overloadings:
+        // [Array a]
+        if args.len() != 1 || self.array_element_type(args[0].ty).is_none() {
+            return Err(CompilationError::WrongBuildin {
+                found: "arr_len is only implemented for 1 (Array) argument".to_string(),
+            });
+        }
+
+        Ok((
+            CustomInstructionCode::ArrLen,
+            self.buildin_types.int as TypeId,
+        ))
+    }
+
+    pub(crate) fn buildin_arr_get(
+        &self,
+        args: &[StaticExpression],
+    ) -> Result<(CustomInstructionCode, TypeId), CompilationError> {
+        // allowed overloadings:
+        // [Array a, Int] -> a
+        let element = match args {
+            [array, index] if index.ty == self.buildin_types.int as TypeId => {
+                self.array_element_type(array.ty)
+            }
+            _ => None,
+        };
+
+        let Some(element) = element else {
+            return Err(CompilationError::WrongBuildin {
+                found: "arr_get is only implemented for (Array, Int) arguments".to_string(),
+            });
+        };
+
+        Ok((CustomInstructionCode::ArrGet, element))
+    }
+
+    pub(crate) fn buildin_env_var(
+        &self,
+        args: &[StaticExpression],
+    ) -> Result<(CustomInstructionCode, TypeId), CompilationError> {
+        // allowed overloadings:
+        // [String]
+        self.assert_type_ids(args, self.buildin_types.string, "String")?;
+
+        if args.len() != 1 {
+            return Err(CompilationError::WrongBuildin {
+                found: "env_var is only implemented for 1 (String) argument".to_string(),
+            });
+        }
+
+        Ok((
+            CustomInstructionCode::EnvVar,
+            self.buildin_types.string as TypeId,
+        ))
+    }
+
+    pub(crate) fn buildin_has_env_var(
+        &self,
+        args: &[StaticExpression],
+    ) -> Result<(CustomInstructionCode, TypeId), CompilationError> {
+        // allowed overloadings:
+        // [String]
+        self.assert_type_ids(args, self.buildin_types.string, "String")?;
+
+        if args.len() != 1 {
+            return Err(CompilationError::WrongBuildin {
+                found: "has_env_var is only implemented for 1 (String) argument".to_string(),
+            });
+        }
+
+        Ok((
+            CustomInstructionCode::HasEnvVar,
+            self.buildin_types.bool as TypeId,
+        ))
+    }
 }
@@ -18,6 +18,11 @@ pub enum CompilationError {
         identifer: String,
         file: String,
     },
+    /// Items of other projects must be declared `pub` to be used
+    PrivateItem {
+        name: String,
+        declaration: String,
+    },
 }
 
 impl std::fmt::Display for CompilationError {
@@ -37,6 +42,10 @@ impl std::fmt::Display for CompilationError {
             Self::CallingVariable { identifer, file } => {
                 write!(f, "tried to call variable {identifer} in {file}. Don't supply arguments to variables, it will be interpreted as a function call.")
             }
+
+            Self::PrivateItem { name, declaration } => {
+                write!(f, "{name} is private, declared at {declaration}. Only items declared `pub` can be used from other projects.")
+            }
         }
     }
 }
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Context;
+
+/// Configuration key for the directories containing installed libraries.
+/// Several directories are separated by `:` and searched in order.
+pub const SOLAR_PATH: &str = "SOLAR_PATH";
+
+/// Configuration key for requiring dependencies to match the solar.lock
+pub const SOLAR_LOCKED: &str = "SOLAR_LOCKED";
+
+/// All configuration keys known to solar, along with their default values.
+const DEFAULTS: &[(&str, &str)] = &[(SOLAR_PATH, "~/.solar/"), (SOLAR_LOCKED, "false")];
+
+/// Files in the working directory and project root, that may contain configuration values.
+/// Files later in the list take precedence.
+const CONFIG_FILES: &[&str] = &[".env", ".solarrc"];
+
+/// Resolved configuration.
+/// Values are merged from several layers, with later layers taking precedence:
+///     defaults -> environment -> .env / .solarrc of the working directory
+///     -> project local .env / .solarrc -> cli args
+pub struct Config {
+    pub project_root: String,
+    /// Roots searched for installed libraries, in order.
+    pub solarpath: Vec<String>,
+    /// Fail instead of updating the solar.lock, if resolved dependencies differ
+    pub locked: bool,
+
+    /// Every known configuration key, its value and the layer it was taken from.
+    pub resolved: Vec<Resolved>,
+}
+
+/// A single configuration value and the layer it was taken from
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub key: &'static str,
+    pub value: String,
+    pub origin: Origin,
+}
+
+/// Layer a configuration value originates from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    Environment,
+    /// Path of the file the value was read from
+    File(String),
+    CommandLine,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::Environment => write!(f, "environment"),
+            Origin::File(path) => write!(f, "file {path}"),
+            Origin::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+impl Config {
+    /// Resolves the configuration for the project at `project_root`.
+    /// `overrides` are the values supplied via command line arguments.
+    pub fn load(project_root: String, overrides: HashMap<String, String>) -> anyhow::Result<Self> {
+        let mut layers = vec![(Origin::Environment, std::env::vars().collect())];
+
+        // in script mode, the project root is a single file.
+        let root = Path::new(&project_root);
+        let dir = if root.is_file() {
+            root.parent().unwrap_or(Path::new("."))
+        } else {
+            root
+        };
+
+        // the working directory may contain config files as well, e.g. at the root of a repository.
+        // Those of the project take precedence.
+        let cwd = Path::new(".");
+        let mut dirs = vec![cwd];
+        if !same_dir(cwd, dir) {
+            dirs.push(dir);
+        }
+
+        for path in dirs
+            .iter()
+            .flat_map(|dir| CONFIG_FILES.iter().map(move |file| dir.join(file)))
+        {
+            if !path.is_file() {
+                continue;
+            }
+
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading config file {}", path.display()))?;
+            let values = parse_env_file(&content);
+
+            layers.push((Origin::File(path.display().to_string()), values));
+        }
+
+        layers.push((Origin::CommandLine, overrides));
+
+        let resolved = DEFAULTS
+            .iter()
+            .map(|&(key, default)| {
+                // search the layer with highest precedence first.
+                layers
+                    .iter()
+                    .rev()
+                    .find_map(|(origin, values)| {
+                        values.get(key).map(|value| Resolved {
+                            key,
+                            value: value.clone(),
+                            origin: origin.clone(),
+                        })
+                    })
+                    .unwrap_or_else(|| Resolved {
+                        key,
+                        value: default.to_string(),
+                        origin: Origin::Default,
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let mut config = Config {
+            project_root,
+            solarpath: Vec::new(),
+            locked: false,
+            resolved,
+        };
+
+        config.solarpath = parse_path_list(config.get(SOLAR_PATH))?;
+        config.locked = parse_bool(config.get(SOLAR_LOCKED))?;
+
+        Ok(config)
+    }
+
+    /// Root of the solarpath, that libraries fetched by solar (e.g. from git) are stored in.
+    /// This is the last one, as the first ones usually are vendored into a project.
+    pub fn cache_root(&self) -> &str {
+        self.solarpath.last().expect("solarpath to contain a root")
+    }
+
+    /// Returns the resolved value of a known configuration key.
+    /// Requesting an unknown key is a programming error.
+    pub fn get(&self, key: &str) -> &Resolved {
+        self.resolved
+            .iter()
+            .find(|r| r.key == key)
+            .expect("configuration key to be known")
+    }
+}
+
+/// Interprets a configuration value as boolean flag.
+fn parse_bool(resolved: &Resolved) -> anyhow::Result<bool> {
+    match resolved.value.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" | "" => Ok(false),
+        other => anyhow::bail!(
+            "expected {} to be true or false, found '{other}' (from {})",
+            resolved.key,
+            resolved.origin
+        ),
+    }
+}
+
+/// Interprets a configuration value as list of directories, separated by `:`.
+/// Empty entries are skipped, but at least one directory is required.
+/// Relative paths read from a config file are relative to the directory of that file.
+fn parse_path_list(resolved: &Resolved) -> anyhow::Result<Vec<String>> {
+    let base = match &resolved.origin {
+        Origin::File(file) => Path::new(file).parent(),
+        _ => None,
+    };
+
+    let paths = resolved
+        .value
+        .split(':')
+        .filter(|path| !path.is_empty())
+        .map(expand_home)
+        .map(|path| match base {
+            Some(base) if Path::new(&path).is_relative() => {
+                let path = path.strip_prefix("./").unwrap_or(&path);
+                base.join(path).display().to_string()
+            }
+            _ => path,
+        })
+        .collect::<Vec<_>>();
+
+    if paths.is_empty() {
+        anyhow::bail!(
+            "expected {} to contain at least one directory (from {})",
+            resolved.key,
+            resolved.origin
+        );
+    }
+
+    Ok(paths)
+}
+
+/// Whether both paths point to the same directory.
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Parses the contents of a .env style file.
+/// Each line contains an assignment `KEY=VALUE`,
+/// optionally prefixed by `export`.
+/// Empty lines and lines starting with `#` are ignored.
+fn parse_env_file(content: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+            .unwrap_or(value);
+
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    values
+}
+
+/// Replaces a leading `~` with the home directory of the user.
+/// Tildes anywhere else in the path are left untouched.
+fn expand_home(path: &str) -> String {
+    let Some(rest) = path.strip_prefix('~') else {
+        return path.to_string();
+    };
+
+    if !(rest.is_empty() || rest.starts_with('/')) {
+        // e.g. ~otheruser/, which we don't support
+        return path.to_string();
+    }
+
+    match std::env::var("HOME") {
+        Ok(home) => home + rest,
+        Err(_) => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_file_assignments() {
+        let content = "
+            # comment
+            SOLAR_PATH = ./vendor:~/.solar
+            export SOLAR_LOCKED=true
+            QUOTED=\"a b\"
+            SINGLE='c'
+            invalid line
+        ";
+        let values = parse_env_file(content);
+
+        assert_eq!(values.len(), 4);
+        assert_eq!(values["SOLAR_PATH"], "./vendor:~/.solar");
+        assert_eq!(values["SOLAR_LOCKED"], "true");
+        assert_eq!(values["QUOTED"], "a b");
+        assert_eq!(values["SINGLE"], "c");
+    }
+
+    #[test]
+    fn expands_leading_tilde_only() {
+        if let Ok(home) = std::env::var("HOME") {
+            assert_eq!(expand_home("~"), home);
+            assert_eq!(expand_home("~/.solar"), format!("{home}/.solar"));
+        }
+
+        assert_eq!(expand_home("~other/.solar"), "~other/.solar");
+        assert_eq!(expand_home("/opt/~/.solar"), "/opt/~/.solar");
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let dir = std::env::temp_dir().join(format!("solar-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(".env"),
+            "SOLAR_PATH=./vendor\nSOLAR_LOCKED=false\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join(".solarrc"), "SOLAR_LOCKED=true\n").unwrap();
+        let root = dir.display().to_string();
+
+        // the project files override those of the working directory and the environment.
+        let config = Config::load(root.clone(), HashMap::new()).unwrap();
+        assert_eq!(
+            config.solarpath,
+            vec![dir.join("vendor").display().to_string()]
+        );
+        assert_eq!(
+            config.get(SOLAR_PATH).origin,
+            Origin::File(dir.join(".env").display().to_string())
+        );
+        assert!(config.locked);
+
+        // command line arguments override everything.
+        let overrides = HashMap::from([(SOLAR_PATH.to_string(), "/cli".to_string())]);
+        let config = Config::load(root, overrides).unwrap();
+        assert_eq!(config.solarpath, vec!["/cli".to_string()]);
+        assert_eq!(config.get(SOLAR_PATH).origin, Origin::CommandLine);
+        assert_eq!(
+            config.get(SOLAR_LOCKED).origin,
+            Origin::File(dir.join(".solarrc").display().to_string())
+        );
+    }
+}
@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Context};
+use solar_parser::ast::body::BodyItem;
+
+use crate::{
+    cli::{Cli, Command, DepsCommand, GraphFormat, ResultOutput, TemplateArgs},
+    compilation::{CompilerContext, FunctionStore},
+    id::{FunctionId, SymbolId, TypeId},
+    mir::{eval::EvaluationContext, Int, Value},
+    project::{
+        read_all_projects, read_modules, read_sources, read_workspace, unused_imports,
+        validate_imports, DependencyGraph, FindError, LoadErrors, ProjectInfo, Scaffold, SourceDb,
+        Workspace,
+    },
+    util, Config,
+};
+
+/// Executes the subcommand given on the command line.
+/// Returns the exit code the process should terminate with.
+pub fn execute(cli: Cli) -> anyhow::Result<ExitCode> {
+    let overrides = cli.config_overrides();
+
+    match cli.command {
+        Command::Run {
+            project,
+            entry,
+            output,
+            args,
+        } => {
+            let config = Config::load(project.dir, overrides)?;
+            let projects =
+                read_all_projects(&config).context("read solar project and dependencies")?;
+
+            compile_entry(
+                &projects,
+                entry.as_deref(),
+                args,
+                |ctx, function_id, args| {
+                    let ctx: EvaluationContext = ctx.into();
+
+                    // The evaluation panics on runtime errors.
+                    // The panic message has already been printed by the time we catch it.
+                    let res =
+                        std::panic::catch_unwind(AssertUnwindSafe(|| ctx.call(function_id, args)))
+                            .map_err(|_| anyhow!("runtime error during evaluation"))?;
+
+                    Ok(exit_with(res, &output))
+                },
+            )
+        }
+        Command::Check(project) => {
+            let config = Config::load(project.dir, overrides)?;
+
+            for projects in read_targets(&config)? {
+                compile_target(&projects, |_| Ok(()))
+                    .with_context(|| format!("checking {}", target_name(&projects)))?;
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Test(project) => {
+            let config = Config::load(project.dir, overrides)?;
+
+            let mut failed = 0;
+            for projects in read_targets(&config)? {
+                failed += run_tests(&projects)
+                    .with_context(|| format!("testing {}", target_name(&projects)))?;
+            }
+
+            if failed > 0 {
+                eprintln!("{failed} tests failed");
+                return Ok(ExitCode::FAILURE);
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Build {
+            project,
+            output,
+            verify_determinism,
+        } => {
+            let config = Config::load(project.dir, overrides)?;
+            let mir = build_mir(&config)?;
+
+            if verify_determinism {
+                let again = build_mir(&config).context("building a second time")?;
+                verify_same_mir(&mir, &again)?;
+                eprintln!("MIR of both builds is identical");
+            }
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, mir).with_context(|| format!("writing MIR to {path}"))?
+                }
+                None => print!("{mir}"),
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::New { dir, template } => {
+            if Path::new(&dir).exists() {
+                bail!("{dir} already exists, use `solar init` to create a project inside of it");
+            }
+
+            scaffold(dir, template, overrides)
+        }
+        Command::Init { dir, template } => scaffold(dir, template, overrides),
+        Command::Deps {
+            command: DepsCommand::Tree { project, format },
+        } => {
+            let config = Config::load(project.dir, overrides)?;
+
+            for projects in read_targets(&config)? {
+                let graph = DependencyGraph::new(&projects);
+
+                let out = match format {
+                    GraphFormat::Text => graph.render_tree(),
+                    GraphFormat::Dot => graph.render_dot(),
+                    GraphFormat::Json => graph.render_json(),
+                };
+                println!("{out}");
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Config { project, show } => {
+            let config = Config::load(project.dir, overrides)?;
+
+            for resolved in &config.resolved {
+                let key = resolved.key;
+                let value = &resolved.value;
+
+                if show {
+                    println!("{key}={value}\t# from {}", resolved.origin);
+                } else {
+                    println!("{key}={value}");
+                }
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// Creates a new project inside `dir`.
+fn scaffold(
+    dir: String,
+    template: TemplateArgs,
+    overrides: HashMap<String, String>,
+) -> anyhow::Result<ExitCode> {
+    let config = Config::load(dir, overrides)?;
+
+    let scaffold = Scaffold {
+        name: template.name.as_deref(),
+        publisher: template.publisher.as_deref(),
+        lib: template.lib,
+    };
+
+    let solar_config = scaffold.create(Path::new(&config.project_root), &config.solarpath)?;
+    eprintln!(
+        "created project {} in {}",
+        solar_config.name, config.project_root
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Maps the value returned by the entry function to the exit code of the process.
+/// Integers become the exit code, all other values are printed,
+/// as specified by the output policy.
+fn exit_with(res: Value, output: &ResultOutput) -> ExitCode {
+    let print = match res {
+        Value::Void => false,
+        Value::Int(_) => output.print_result,
+        _ => !output.quiet,
+    };
+
+    if print {
+        println!("{res}");
+    }
+
+    let Value::Int(code) = res else {
+        return ExitCode::SUCCESS;
+    };
+
+    match u8::try_from(code.as_i128()) {
+        Ok(code) => ExitCode::from(code),
+        Err(_) => {
+            eprintln!("returned {code}, which is not a valid exit code (0 - 255)");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads the target projects at the project root, along with their dependencies.
+/// That is every member for workspaces, or the single project otherwise.
+fn read_targets(config: &Config) -> anyhow::Result<Vec<ProjectInfo>> {
+    let targets = match Workspace::read(&config.project_root)? {
+        Some(workspace) => read_workspace(config, &workspace),
+        None => read_all_projects(config).map(|projects| vec![projects]),
+    };
+
+    targets.context("read solar project and dependencies")
+}
+
+/// Reads and compiles all target projects,
+/// returning the MIR of each one after another.
+fn build_mir(config: &Config) -> anyhow::Result<String> {
+    let targets = read_targets(config)?;
+
+    let mut mir = String::new();
+    for projects in &targets {
+        // the MIR of workspace members is written one after another.
+        if targets.len() > 1 {
+            writeln!(mir, "# {}\n", target_name(projects)).expect("write to string");
+        }
+
+        compile_target(projects, |ctx| {
+            let functions = ctx.functions.into_inner().expect("locking functions");
+            mir.push_str(&render_mir(&functions));
+            Ok(())
+        })
+        .with_context(|| format!("building {}", target_name(projects)))?;
+    }
+
+    Ok(mir)
+}
+
+/// Fails with the first difference, if two builds resulted in different MIR.
+fn verify_same_mir(first: &str, second: &str) -> anyhow::Result<()> {
+    if first == second {
+        return Ok(());
+    }
+
+    let mut first_lines = first.lines();
+    let mut second_lines = second.lines();
+    for line in 1.. {
+        let (a, b) = (first_lines.next(), second_lines.next());
+        if a != b {
+            bail!(
+                "MIR differs between two builds, starting at line {line}:\n  first:  {}\n  second: {}",
+                a.unwrap_or("<end of MIR>"),
+                b.unwrap_or("<end of MIR>")
+            );
+        }
+    }
+
+    unreachable!("differing MIR to have a differing line")
+}
+
+/// Name of the target project, e.g. to tell workspace members apart.
+fn target_name(projects: &ProjectInfo) -> String {
+    projects
+        .get_by_key(&util::target_id())
+        .map(|(_, p)| format!("{} at {}", p.config.name, p.fsroot))
+        .expect("target project to be read in")
+}
+
+/// Parses all modules and checks their imports.
+/// The resulting context is handed over to `then`,
+/// because it borrows from the projects and source files.
+fn load<T>(
+    project_info: &ProjectInfo,
+    then: impl FnOnce(&SourceDb, CompilerContext<'_>) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    // keep going past broken files, to report all of them at once.
+    let mut errors = LoadErrors::default();
+    let sources = read_sources(project_info, &mut errors);
+    let modules = read_modules(project_info, &sources, &mut errors);
+    errors.into_result()?;
+
+    // imports are only checked, once all files could be read.
+    // Otherwise modules missing due to a broken file would be reported as well.
+    let mut errors = LoadErrors::default();
+    validate_imports(&modules, &sources, &mut errors);
+    errors.into_result()?;
+
+    let ctx = CompilerContext::with_default_io(project_info, modules);
+    then(&sources, ctx)
+}
+
+/// Prints a warning for every import of the target project,
+/// that hasn't been used while compiling.
+/// Imports of files with functions, that haven't been compiled, aren't reported.
+fn warn_unused_imports(ctx: &CompilerContext, sources: &SourceDb) {
+    let used_imports = ctx.used_imports.read().expect("locking used imports");
+    let functions = ctx.functions.read().expect("locking functions");
+    let is_compiled = |symbol_id: &SymbolId| functions.contains_symbol(symbol_id);
+
+    for warning in unused_imports(&ctx.module_info, sources, &used_imports, is_compiled) {
+        eprintln!("warning: {warning}");
+    }
+}
+
+/// Runs the pipeline of parsing all modules
+/// and compiling the entry function of the target project.
+/// Without an explicit entry (e.g. `util.greet`), the main function is compiled.
+/// The compiled context is handed over to `then`,
+/// because it borrows from the projects and modules.
+/// Along with it, the arguments to call the entry function with are supplied.
+fn compile_entry<T>(
+    project_info: &ProjectInfo,
+    entry: Option<&str>,
+    program_args: Vec<String>,
+    then: impl FnOnce(CompilerContext<'_>, FunctionId, Vec<Value>) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    load(project_info, |sources, ctx| {
+        let (symbol_id, (arg_types, args)) = match entry {
+            None => {
+                let symbol_id = ctx.find_target_main().context("find main function")?;
+                let args = main_arguments(&ctx, &symbol_id, program_args)?;
+                (symbol_id, args)
+            }
+            Some(entry) => {
+                let path = entry.split('.').map(String::from).collect::<Vec<_>>();
+                let symbol_id = ctx
+                    .find_target_function(&path)
+                    .with_context(|| format!("find entry function {entry}"))?;
+                let args = entry_arguments(&ctx, &symbol_id, program_args)
+                    .with_context(|| format!("passing arguments to {entry}"))?;
+                (symbol_id, args)
+            }
+        };
+
+        let (function_id, _ret_type_id) = ctx
+            .compile_symbol(symbol_id, &arg_types)
+            .context("compile code")?;
+
+        warn_unused_imports(&ctx, sources);
+        then(ctx, function_id, args)
+    })
+}
+
+/// Compiles the main function of the target project, if there is one.
+/// Libraries don't need a main function,
+/// their modules are only read in and their imports checked.
+/// The compiled context is handed over to `then`.
+fn compile_target<T>(
+    project_info: &ProjectInfo,
+    then: impl FnOnce(CompilerContext<'_>) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    load(project_info, |sources, ctx| {
+        match ctx.find_target_main() {
+            Ok(symbol_id) => {
+                let (arg_types, _) = main_arguments(&ctx, &symbol_id, Vec::new())?;
+                ctx.compile_symbol(symbol_id, &arg_types)
+                    .context("compile code")?;
+            }
+            Err(FindError::NotFound(_) | FindError::ModuleNotFound(_)) => {
+                eprintln!(
+                    "note: {} has no main function, only checked its imports",
+                    target_name(project_info)
+                );
+            }
+            Err(e) => return Err(e).context("find main function"),
+        }
+
+        warn_unused_imports(&ctx, sources);
+        then(ctx)
+    })
+}
+
+/// Compiles and runs all tests of the target project.
+/// Every test is run, even if a previous one failed.
+/// Returns the number of failed tests.
+fn run_tests(project_info: &ProjectInfo) -> anyhow::Result<usize> {
+    load(project_info, |sources, ctx| {
+        let targets = ctx.find_target_tests();
+        eprintln!(
+            "running {} tests of {}",
+            targets.len(),
+            target_name(project_info)
+        );
+
+        let mut tests = Vec::new();
+        for (name, symbol_id) in targets {
+            let (function_id, _ret_type_id) = ctx
+                .compile_symbol(symbol_id, &[])
+                .with_context(|| format!("compile test {name}"))?;
+            tests.push((name, function_id));
+        }
+
+        warn_unused_imports(&ctx, sources);
+
+        let ctx: EvaluationContext = ctx.into();
+        let mut failed = 0;
+        for (name, function_id) in tests {
+            // The evaluation panics on runtime errors.
+            // The panic message has already been printed by the time we catch it.
+            let res =
+                std::panic::catch_unwind(AssertUnwindSafe(|| ctx.call(function_id, Vec::new())));
+
+            match res {
+                Ok(Value::Bool(false)) | Err(_) => {
+                    eprintln!("test {name} ... FAILED");
+                    failed += 1;
+                }
+                Ok(_) => eprintln!("test {name} ... ok"),
+            }
+        }
+
+        Ok(failed)
+    })
+}
+
+/// Arguments to call the main function with.
+/// main may either take no arguments,
+/// or a single Array of Strings, containing the program arguments.
+fn main_arguments(
+    ctx: &CompilerContext,
+    main_symbol_id: &SymbolId,
+    program_args: Vec<String>,
+) -> anyhow::Result<(Vec<TypeId>, Vec<Value>)> {
+    let (_, _, item) = ctx.get_symbol(main_symbol_id.clone());
+    let BodyItem::Function(main) = item else {
+        bail!("main is expected to be a function");
+    };
+
+    match main.args.as_slice() {
+        [] => Ok((Vec::new(), Vec::new())),
+        [(ident, ty)] => {
+            let declared = ty.as_ref().map(|ty| ty.span.trim());
+            if declared != Some("Array") {
+                bail!(
+                    "the parameter {} of main must be declared as Array, found {}",
+                    ident.value,
+                    declared.unwrap_or("no type")
+                );
+            }
+
+            let Some(ty) = ctx.buildin_types.string_array else {
+                bail!("main takes an Array, but std doesn't declare buildin_type Array");
+            };
+            let ty = ty as TypeId;
+            let args = program_args.into_iter().map(Value::String).collect();
+
+            Ok((vec![ty], vec![Value::Array(args)]))
+        }
+        args => bail!(
+            "main must take no arguments or an Array of Strings, but takes {} arguments",
+            args.len()
+        ),
+    }
+}
+
+/// Arguments to call an arbitrary function with.
+/// Every program argument is parsed as literal of the declared type
+/// of the parameter at the same position.
+fn entry_arguments(
+    ctx: &CompilerContext,
+    symbol_id: &SymbolId,
+    program_args: Vec<String>,
+) -> anyhow::Result<(Vec<TypeId>, Vec<Value>)> {
+    let (_, _, item) = ctx.get_symbol(symbol_id.clone());
+    let BodyItem::Function(function) = item else {
+        bail!("entry is expected to be a function");
+    };
+
+    if function.args.len() != program_args.len() {
+        bail!(
+            "expected {} arguments, got {}",
+            function.args.len(),
+            program_args.len()
+        );
+    }
+
+    let types = &ctx.buildin_types;
+    let mut arg_types = Vec::new();
+    let mut args = Vec::new();
+
+    for ((ident, ty), arg) in function.args.iter().zip(program_args) {
+        let Some(ty) = ty else {
+            bail!("parameter {} has no declared type", ident.value);
+        };
+
+        let (value, ty) = match ty.span.trim() {
+            "String" => (Value::String(arg), types.string),
+            "Int" => {
+                let i = arg
+                    .parse::<i64>()
+                    .with_context(|| format!("parsing {arg} as Int"))?;
+                (Value::Int(Int::Int64(i)), types.int)
+            }
+            "Float" => {
+                let f = arg
+                    .parse::<f64>()
+                    .with_context(|| format!("parsing {arg} as Float"))?;
+                (Value::Float(f), types.float)
+            }
+            "Bool" => {
+                let b = arg
+                    .parse::<bool>()
+                    .with_context(|| format!("parsing {arg} as Bool"))?;
+                (Value::Bool(b), types.bool)
+            }
+            other => bail!(
+                "parameter {} has type {other}, which can't be passed from the command line",
+                ident.value
+            ),
+        };
+
+        arg_types.push(ty as TypeId);
+        args.push(value);
+    }
+
+    Ok((arg_types, args))
+}
+
+/// Human readable representation of all compiled functions.
+fn render_mir(functions: &FunctionStore) -> String {
+    let mut mir = String::new();
+
+    for (id, info) in functions.iter_values() {
+        writeln!(mir, "fn #{id}\n{info:#?}\n").expect("write to string");
+    }
+
+    mir
+}
@@ -4,4 +4,8 @@ pub enum CustomInstructionCode {
     Print,
     Identity,
     Readline,
+    ArrLen,
+    ArrGet,
+    EnvVar,
+    HasEnvVar,
 }
@@ -1,6 +1,6 @@
 use crate::compilation::FunctionInfo;
 use crate::mir::{CustomInstructionCode, Instruction};
-use crate::mir::{StaticExpression, Value};
+use crate::mir::{Int, StaticExpression, Value};
 
 use std::cell::RefCell;
 
@@ -105,6 +105,37 @@ impl EvaluationContext {
                     std::io::stdin().read_line(&mut buf).expect("read line");
                     Value::String(buf)
                 }
+                CustomInstructionCode::ArrLen => {
+                    let Value::Array(values) = self.eval_expression(&args[0]) else {
+                        panic!("expect argument of arr_len to be an array");
+                    };
+                    Value::Int(Int::Int64(values.len() as i64))
+                }
+                CustomInstructionCode::ArrGet => {
+                    let Value::Array(mut values) = self.eval_expression(&args[0]) else {
+                        panic!("expect first argument of arr_get to be an array");
+                    };
+                    let Value::Int(index) = self.eval_expression(&args[1]) else {
+                        panic!("expect second argument of arr_get to be an integer");
+                    };
+
+                    let len = values.len();
+                    let index = index.as_i128();
+                    if index < 0 || index >= len as i128 {
+                        panic!("index {index} out of bounds for array of length {len}");
+                    }
+
+                    values.swap_remove(index as usize)
+                }
+                CustomInstructionCode::EnvVar => {
+                    // unset variables yield an empty string
+                    let name = self.string_concat(args);
+                    Value::String(std::env::var(name).unwrap_or_default())
+                }
+                CustomInstructionCode::HasEnvVar => {
+                    let name = self.string_concat(args);
+                    Value::Bool(std::env::var_os(name).is_some())
+                }
             },
             Instruction::FunctionCall { func_id, args } => {
                 let args: Vec<Value> = args
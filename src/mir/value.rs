@@ -10,6 +10,7 @@ pub enum Value {
     Int(Int),
     Float(f64),
     String(String),
+    Array(Vec<Value>),
 }
 
 impl Value {
@@ -24,6 +25,7 @@ impl Value {
             Value::Int(_) => "Int",
             Value::Float(_) => "Float",
             Value::String(_) => "String",
+            Value::Array(_) => "Array",
         }
     }
 }
@@ -44,6 +46,16 @@ impl fmt::Display for Value {
             Value::Int(i) => write!(f, "{i}"),
             Value::Float(i) => write!(f, "{i}"),
             Value::String(i) => write!(f, "{i}"),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            }
         }
     }
 }
@@ -60,6 +72,23 @@ pub enum Int {
     Uint8(u8),
 }
 
+impl Int {
+    /// Widens the integer, so that every variant fits.
+    pub fn as_i128(self) -> i128 {
+        use Int::*;
+        match self {
+            Int64(v) => v as i128,
+            Int32(v) => v as i128,
+            Int16(v) => v as i128,
+            Int8(v) => v as i128,
+            Uint64(v) => v as i128,
+            Uint32(v) => v as i128,
+            Uint16(v) => v as i128,
+            Uint8(v) => v as i128,
+        }
+    }
+}
+
 impl fmt::Display for Int {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Int::*;
@@ -0,0 +1,214 @@
+/// This file contains code
+/// for fetching libraries from git repositories
+/// into a cache inside the solarpath.
+///
+/// Every repository is mirrored once into `git/db/<repo>`,
+/// every requested commit is checked out into `git/checkouts/<repo>/<commit>`.
+/// Both are reused by later runs, so no network access is needed
+/// as long as the requested revision is already known.
+use anyhow::{bail, Context};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::process::Command;
+
+/// A revision of a git repository, checked out inside the solarpath
+#[derive(Debug, Clone)]
+pub struct GitCheckout {
+    /// Url the repository has been cloned from
+    pub url: String,
+    /// Full hash of the checked out commit
+    pub commit: String,
+    /// Directory containing the checked out files
+    pub dir: String,
+}
+
+/// Checks out `rev` (a tag or commit) of the repository `repo` into the cache of the solarpath.
+/// Tags may be written with or without leading `v`, e.g. `0.1.0` finds the tag `v0.1.0`.
+pub fn checkout(solarpath: &str, repo: &str, rev: &str) -> anyhow::Result<GitCheckout> {
+    // both are passed on to git, which would read them as options otherwise.
+    // They might come from the solar.yaml of any (transitive) dependency.
+    if repo.starts_with('-') {
+        bail!("invalid git repository '{repo}', it must not start with '-'");
+    }
+    if rev.starts_with('-') {
+        bail!("invalid git revision '{rev}', it must not start with '-'");
+    }
+
+    let url = repo_url(repo);
+    let name = cache_name(&url);
+
+    let cache = Path::new(solarpath).join("git");
+    let db = cache.join("db").join(&name);
+    let db = db.to_str().context("read path of git cache")?;
+
+    if !Path::new(db).exists() {
+        git(None, &["clone", "--quiet", "--bare", "--", &url, db])
+            .with_context(|| format!("cloning {url}"))?;
+    }
+
+    // only go online, if the revision isn't known yet.
+    let commit = match find_commit(db, rev) {
+        Some(commit) => commit,
+        None => {
+            git(
+                Some(db),
+                &[
+                    "fetch",
+                    "--quiet",
+                    "--tags",
+                    "--force",
+                    "--",
+                    &url,
+                    "+refs/heads/*:refs/heads/*",
+                ],
+            )
+            .with_context(|| format!("fetching {url}"))?;
+
+            find_commit(db, rev)
+                .with_context(|| format!("no tag or commit '{rev}' found in {url}"))?
+        }
+    };
+
+    let dir = cache.join("checkouts").join(&name).join(&commit);
+    let dir = dir.to_str().context("read path of git cache")?.to_string();
+
+    if !Path::new(&dir).exists() {
+        // check out into a temporary directory first,
+        // so that an interrupted checkout is never mistaken for a complete one.
+        let tmp = format!("{dir}.tmp");
+        if Path::new(&tmp).exists() {
+            std::fs::remove_dir_all(&tmp).with_context(|| format!("removing {tmp}"))?;
+        }
+
+        git(None, &["clone", "--quiet", "--no-checkout", "--", db, &tmp])
+            .with_context(|| format!("checking out {url} at {commit}"))?;
+        git(Some(&tmp), &["checkout", "--quiet", "--detach", &commit])
+            .with_context(|| format!("checking out {url} at {commit}"))?;
+
+        // the history isn't part of the library.
+        std::fs::remove_dir_all(Path::new(&tmp).join(".git"))
+            .with_context(|| format!("cleaning up checkout {tmp}"))?;
+        std::fs::rename(&tmp, &dir).with_context(|| format!("moving checkout to {dir}"))?;
+    }
+
+    Ok(GitCheckout { url, commit, dir })
+}
+
+/// Url to clone a repository from.
+/// Repositories without a scheme, like `github.com/solar-lang/std`, are fetched via https.
+/// Urls with a scheme, scp-like urls (`git@host:path`) and local paths are left untouched.
+fn repo_url(repo: &str) -> String {
+    let is_local = repo.starts_with('/') || repo.starts_with('.');
+    let is_scp = repo
+        .split_once(':')
+        .is_some_and(|(host, _)| host.contains('@'));
+
+    if repo.contains("://") || is_local || is_scp {
+        return repo.to_string();
+    }
+
+    format!("https://{repo}")
+}
+
+/// Name of the directory a repository is cached in.
+/// The hash keeps repositories with the same name apart.
+fn cache_name(url: &str) -> String {
+    let hash = Sha256::digest(url.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    let name = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default();
+
+    format!("{name}-{hash}")
+}
+
+/// Finds the full hash of the commit a tag or (abbreviated) commit hash refers to.
+fn find_commit(db: &str, rev: &str) -> Option<String> {
+    let candidates = [
+        format!("refs/tags/{rev}"),
+        format!("refs/tags/v{rev}"),
+        rev.to_string(),
+    ];
+
+    candidates.iter().find_map(|candidate| {
+        let spec = format!("{candidate}^{{commit}}");
+        git(Some(db), &["rev-parse", "--quiet", "--verify", &spec]).ok()
+    })
+}
+
+/// Runs git and returns its trimmed output.
+/// Fails with the error output of git, if the command doesn't succeed.
+fn git(dir: Option<&str>, args: &[&str]) -> anyhow::Result<String> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+
+    let output = cmd
+        .args(args)
+        .output()
+        .context("running git, is it installed?")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repo_url_defaults_to_https() {
+        assert_eq!(
+            repo_url("github.com/solar-lang/std"),
+            "https://github.com/solar-lang/std"
+        );
+        assert_eq!(
+            repo_url("ssh://git@github.com/acme/lib"),
+            "ssh://git@github.com/acme/lib"
+        );
+        assert_eq!(
+            repo_url("git@github.com:acme/lib"),
+            "git@github.com:acme/lib"
+        );
+        assert_eq!(repo_url("../mylib"), "../mylib");
+        assert_eq!(repo_url("/srv/git/other.git"), "/srv/git/other.git");
+    }
+
+    #[test]
+    fn cache_name_keeps_repos_apart() {
+        let std = cache_name("https://github.com/solar-lang/std.git");
+        assert!(std.starts_with("std-"), "{std}");
+        assert_eq!(std, cache_name("https://github.com/solar-lang/std.git"));
+
+        let scp = cache_name("git@github.com:acme/lib");
+        assert!(scp.starts_with("lib-"), "{scp}");
+
+        let fork = cache_name("https://github.com/acme/std");
+        assert!(fork.starts_with("std-"), "{fork}");
+        assert_ne!(std, fork);
+    }
+
+    #[test]
+    fn checkout_rejects_options() {
+        let e = checkout("/nonexistent", "--upload-pack=evil", "v1").unwrap_err();
+        assert!(e.to_string().contains("must not start with '-'"));
+
+        let e = checkout("/nonexistent", "github.com/acme/lib", "--output=x").unwrap_err();
+        assert!(e.to_string().contains("must not start with '-'"));
+    }
+}
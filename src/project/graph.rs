@@ -0,0 +1,252 @@
+/// This file contains code
+/// for inspecting the resolved dependencies of a project,
+/// printed as tree or exported as graph.
+use super::{Project, ProjectInfo};
+use crate::util::{self, IdPath};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// All projects that have been read in, along with their dependencies.
+#[derive(Debug, Serialize)]
+pub struct DependencyGraph {
+    /// Every project, the target project comes first.
+    pub nodes: Vec<Node>,
+    /// Dependencies between projects, labeled with the alias used for importing.
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Node {
+    /// Unique Id of the project
+    pub basepath: IdPath,
+    pub name: String,
+    pub publisher: String,
+    pub version: String,
+    /// Root in filesystem
+    pub fsroot: String,
+    /// Aliases used by imports inside the project, mapped to the basepath of the dependency.
+    pub dep_map: BTreeMap<String, IdPath>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub from: IdPath,
+    pub to: IdPath,
+    /// Name the dependency is imported with, e.g. `std` in `use @std.io`
+    pub alias: String,
+}
+
+/// A library, that is used in more than one version
+struct Duplicate<'g> {
+    /// `name(publisher)` of the library
+    library: String,
+    /// Every version, along with the projects depending on it
+    versions: Vec<(&'g Node, Vec<String>)>,
+}
+
+impl DependencyGraph {
+    pub fn new(projects: &ProjectInfo) -> Self {
+        // keep the order of the HotelMap, except for the target
+        // (which is read in last, after all its dependencies).
+        let mut nodes = projects
+            .iter_values()
+            .map(|(_, p)| Node::new(p))
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|n| n.basepath != util::target_id());
+
+        let edges = nodes
+            .iter()
+            .flat_map(|n| {
+                n.dep_map.iter().map(|(alias, to)| Edge {
+                    from: n.basepath.clone(),
+                    to: to.clone(),
+                    alias: alias.clone(),
+                })
+            })
+            .collect();
+
+        DependencyGraph { nodes, edges }
+    }
+
+    fn node(&self, basepath: &IdPath) -> Option<&Node> {
+        self.nodes.iter().find(|n| &n.basepath == basepath)
+    }
+
+    /// Renders the dependencies as tree, starting at the target project.
+    /// Projects that have been expanded before are marked with `(*)`.
+    /// Libraries used in more than one version are listed at the end,
+    /// along with the projects that depend on each version.
+    pub fn render_tree(&self) -> String {
+        let mut out = String::new();
+
+        let Some(root) = self.nodes.first() else {
+            return out;
+        };
+
+        writeln!(out, "{}", root.label()).expect("write to string");
+        let mut expanded = HashSet::from([&root.basepath]);
+        self.render_children(root, "", &mut expanded, &mut out);
+
+        for Duplicate { library, versions } in self.duplicates() {
+            writeln!(out, "\n{library} is used in {} versions:", versions.len())
+                .expect("write to string");
+
+            for (node, dependents) in versions {
+                writeln!(
+                    out,
+                    "    {} required by {}",
+                    node.version,
+                    dependents.join(", ")
+                )
+                .expect("write to string");
+            }
+        }
+
+        out
+    }
+
+    fn render_children<'a>(
+        &'a self,
+        node: &'a Node,
+        indent: &str,
+        expanded: &mut HashSet<&'a IdPath>,
+        out: &mut String,
+    ) {
+        let count = node.dep_map.len();
+
+        for (i, (alias, basepath)) in node.dep_map.iter().enumerate() {
+            let last = i + 1 == count;
+            let (branch, next_indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            let Some(dep) = self.node(basepath) else {
+                writeln!(
+                    out,
+                    "{indent}{branch}{alias}: {} (missing)",
+                    basepath.join("/")
+                )
+                .expect("write to string");
+                continue;
+            };
+
+            let seen = !expanded.insert(&dep.basepath);
+            let marker = if seen && !dep.dep_map.is_empty() {
+                " (*)"
+            } else {
+                ""
+            };
+            writeln!(out, "{indent}{branch}{alias}: {}{marker}", dep.label())
+                .expect("write to string");
+
+            if !seen {
+                let indent = format!("{indent}{next_indent}");
+                self.render_children(dep, &indent, expanded, out);
+            }
+        }
+    }
+
+    /// Libraries, that are used in more than one version,
+    /// along with the projects depending on each version.
+    fn duplicates(&self) -> Vec<Duplicate<'_>> {
+        let mut libraries: BTreeMap<String, Vec<&Node>> = BTreeMap::new();
+        for node in &self.nodes[1..] {
+            // the first part of the basepath is `name(publisher)`
+            let library = node.basepath[0].clone();
+            libraries.entry(library).or_default().push(node);
+        }
+
+        libraries
+            .into_iter()
+            .filter(|(_, versions)| versions.len() > 1)
+            .map(|(library, versions)| {
+                let versions = versions
+                    .into_iter()
+                    .map(|node| {
+                        let dependents = self
+                            .edges
+                            .iter()
+                            .filter(|e| e.to == node.basepath)
+                            .filter_map(|e| self.node(&e.from))
+                            .map(|n| format!("{} {}", n.name, n.version))
+                            .collect();
+
+                        (node, dependents)
+                    })
+                    .collect();
+
+                Duplicate { library, versions }
+            })
+            .collect()
+    }
+
+    /// Renders the graph in the DOT language of graphviz.
+    pub fn render_dot(&self) -> String {
+        let mut out = String::from("digraph dependencies {\n");
+
+        for node in &self.nodes {
+            writeln!(
+                out,
+                "    {} [label={}];",
+                quote(&node.basepath.join("/")),
+                quote(&format!(
+                    "{}({}) {}\\n{}",
+                    node.name, node.publisher, node.version, node.fsroot
+                ))
+            )
+            .expect("write to string");
+        }
+
+        for edge in &self.edges {
+            writeln!(
+                out,
+                "    {} -> {} [label={}];",
+                quote(&edge.from.join("/")),
+                quote(&edge.to.join("/")),
+                quote(&edge.alias)
+            )
+            .expect("write to string");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn render_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serialize dependency graph")
+    }
+}
+
+impl Node {
+    fn new(p: &Project) -> Self {
+        Node {
+            basepath: p.basepath.clone(),
+            name: p.config.name.clone(),
+            publisher: p.config.publisher.clone().unwrap_or_default(),
+            version: p.config.version.clone(),
+            fsroot: p.fsroot.clone(),
+            dep_map: p.dep_map.clone().into_iter().collect(),
+        }
+    }
+
+    /// e.g. `std(solar-lang) 0.0.1 [std(solar-lang)/0.0.1] ~/.solar/libraries/std(solar-lang)/0.0.1`
+    fn label(&self) -> String {
+        format!(
+            "{}({}) {} [{}] {}",
+            self.name,
+            self.publisher,
+            self.version,
+            self.basepath.join("/"),
+            self.fsroot
+        )
+    }
+}
+
+/// Quoted string in the DOT language.
+/// Sequences like `\n` are kept, so labels may span several lines.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
@@ -0,0 +1,156 @@
+/// This file contains code
+/// for checking the imports of all files against the modules that have been read in.
+use super::{FileId, FileInfo, GlobalModules, ImportItem, LoadErrors, SourceDb};
+use crate::id::{IdItem, SymbolId};
+use crate::util::{self, IdPath};
+use solar_parser::ast::body::BodyItem;
+use std::collections::HashSet;
+
+/// An import, that has been consulted while resolving a symbol.
+/// (file containing the import, imported symbol or None for wildcard imports, module imported from)
+pub type UsedImport = (FileId, Option<String>, IdPath);
+
+/// Checks, that every imported module and symbol exists,
+/// and that symbols of other projects are declared `pub`.
+/// Imports that can't be found are reported in `errors`,
+/// pointing at the `use` statement.
+pub fn validate_imports(modules: &GlobalModules, sources: &SourceDb, errors: &mut LoadErrors) {
+    for module in modules.values() {
+        for file in &module.files {
+            for item in &file.import_items {
+                if let Err(message) = check_import(modules, item, module.project_id) {
+                    errors.push(anyhow::anyhow!(sources.diagnostic(item.span, message)));
+                }
+            }
+        }
+    }
+}
+
+/// `project_id` is the project containing the import.
+fn check_import(
+    modules: &GlobalModules,
+    item: &ImportItem,
+    project_id: usize,
+) -> Result<(), String> {
+    let module = modules.get(&item.module);
+
+    let Some(symbol) = &item.symbol else {
+        // wildcard imports only need the module to exist.
+        return match module {
+            Some(_) => Ok(()),
+            None => Err(format!("module {:?} not found", item.module)),
+        };
+    };
+
+    // `use models.customer` might import the module `customer` instead of a symbol,
+    // e.g. to call `customer.create()`
+    let mut submodule = item.module.clone();
+    submodule.push(symbol.clone());
+    if modules.contains_key(&submodule) {
+        return Ok(());
+    }
+
+    let Some(module) = module else {
+        return Err(format!("module {:?} not found", item.module));
+    };
+
+    let Ok(candidates) = module.find(symbol) else {
+        return Err(format!("{symbol} not found in module {:?}", item.module));
+    };
+
+    // items of other projects have to be declared `pub`.
+    if candidates
+        .iter()
+        .any(|c| module.is_visible_from(c, project_id))
+    {
+        return Ok(());
+    }
+
+    Err(format!(
+        "{symbol} is private, declared at {}",
+        module.declaration(&candidates[0])
+    ))
+}
+
+/// Renders a warning for every import of the target project,
+/// that hasn't been consulted while compiling.
+/// Imports of dependencies are not reported.
+/// Only files, whose functions have all been compiled, are checked,
+/// as the others might use their imports in a function that hasn't been compiled.
+pub fn unused_imports(
+    modules: &GlobalModules,
+    sources: &SourceDb,
+    used: &HashSet<UsedImport>,
+    is_compiled: impl Fn(&SymbolId) -> bool,
+) -> Vec<String> {
+    let target = util::target_id();
+    let mut warnings = Vec::new();
+
+    for (idmodule, module) in modules {
+        if !idmodule.starts_with(&target) {
+            continue;
+        }
+
+        for (idfile, file) in module.files.iter().enumerate() {
+            let idfile = idfile as u16;
+            if !is_fully_compiled(idmodule, idfile, file, &is_compiled) {
+                continue;
+            }
+
+            for item in &file.import_items {
+                let key = (file.file_id, item.symbol.clone(), item.module.clone());
+                if used.contains(&key) {
+                    continue;
+                }
+
+                // type annotations aren't resolved while compiling (yet),
+                // so imports of types can't be told apart from unused ones.
+                if imports_type(modules, item) {
+                    continue;
+                }
+
+                let message = match &item.symbol {
+                    Some(symbol) => format!("unused import {symbol}"),
+                    None => format!("unused wildcard import of module {:?}", item.module),
+                };
+                warnings.push(sources.diagnostic(item.span, message));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Whether every function of the file has been compiled.
+/// Global variables are never compiled (yet), so files containing them aren't either.
+fn is_fully_compiled(
+    idmodule: &IdPath,
+    idfile: u16,
+    file: &FileInfo,
+    is_compiled: impl Fn(&SymbolId) -> bool,
+) -> bool {
+    file.ast
+        .items
+        .iter()
+        .enumerate()
+        .all(|(iditem, item)| match item {
+            BodyItem::Function(_) => {
+                is_compiled(&(idmodule.clone(), idfile, IdItem::Func(iditem as u16)))
+            }
+            BodyItem::Let(_) => false,
+            _ => true,
+        })
+}
+
+/// Whether the import refers to a type, e.g. `use @std.types.int.Int`
+fn imports_type(modules: &GlobalModules, item: &ImportItem) -> bool {
+    let (Some(symbol), Some(module)) = (&item.symbol, modules.get(&item.module)) else {
+        return false;
+    };
+
+    module.find(symbol).is_ok_and(|candidates| {
+        candidates
+            .iter()
+            .any(|(_, _, i)| matches!(i, IdItem::Type(_)))
+    })
+}
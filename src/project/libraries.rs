@@ -1,11 +1,14 @@
-use super::Module;
 /// This file contains code
 /// for reading in dependencies and libraries.
 /// and resolving their imports.
-use crate::project::{FileInfo, SolarConfig};
+use crate::project::{
+    FileId, FileInfo, Lockfile, ResolvedDependency, SolarConfig, SourceFile, SourceFilter,
+};
 use crate::util::IdPath;
+use crate::Config;
 use anyhow::Context;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 /// Contains information on a project,
@@ -21,8 +24,15 @@ pub struct Project {
     /// dependencies
     pub dep_map: HashMap<String, IdPath>,
 
+    /// Dependencies of this project, resolved to concrete libraries
+    pub deps: Vec<ResolvedDependency>,
+
     /// Solarconfig of this project
     pub config: SolarConfig,
+
+    /// Set, if the project consists of just this one solar file.
+    /// (Script mode, without a solar.yaml)
+    pub single_file: Option<String>,
 }
 
 impl Project {
@@ -34,104 +44,199 @@ impl Project {
         // the basepath is the unique identifier for this project.
         // For libraries it is supposed to match the root.
         basepath: IdPath,
+        config: &Config,
+        // versions to prefer when resolving dependencies
+        lock: Option<&Lockfile>,
     ) -> anyhow::Result<Project> {
         let solarfile = format!("{fsroot}/solar.yaml");
 
         // solar config file of the project
-        let config = SolarConfig::read(&solarfile)?;
+        let solar_config = SolarConfig::read(&solarfile)?;
         let fsroot = fsroot.to_string();
 
-        let dep_map = config
-            .deps()
-            .into_iter()
-            .map(|d| {
-                let value = d.basepath();
-                let key = d.name;
-                (key, value)
-            })
-            .collect();
+        let deps = solar_config.resolve_deps(&fsroot, config, lock)?;
+        let dep_map = dep_map(&deps);
 
         Ok(Project {
             basepath,
             fsroot,
             dep_map,
-            config,
+            deps,
+            config: solar_config,
+            single_file: None,
         })
     }
 
-    /// Reads and parses
-    /// all solarfiles, organized into modules,
-    /// into memory.
-    /// the project id is supposed to be a reference to this exact [Project].
-    ///
-    /// Returns a Mapping of  {ModulePath => Module}
-    pub fn read_all(&self, project_id: usize) -> anyhow::Result<HashMap<IdPath, Module<'_>>> {
-        let mut map = HashMap::new();
-
-        for entry in WalkDir::new(&self.fsroot) {
-            let Ok(entry) = entry else {
-                eprintln!("error walking directory: {entry:?}");
-                continue;
+    /// Creates an implicit project for a single solar file,
+    /// that is not accompanied by a solar.yaml (script mode).
+    /// The project depends on the installed std library, if there is any.
+    pub fn script(
+        // path of the solar file
+        file: &str,
+        // unique identifier for this project.
+        basepath: IdPath,
+        config: &Config,
+    ) -> anyhow::Result<Project> {
+        let path = Path::new(file);
+        if !path.is_file() {
+            anyhow::bail!("solar file {file} not found");
+        }
+
+        let name = path
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .context("read name of solar file")?;
+
+        let solar_config = SolarConfig::implicit(name, &config.solarpath);
+
+        // The root of the project is the directory containing the file
+        let fsroot = match path.parent().and_then(|p| p.to_str()) {
+            Some("") | None => ".".to_string(),
+            Some(dir) => dir.to_string(),
+        };
+
+        // scripts have no lockfile
+        let deps = solar_config.resolve_deps(&fsroot, config, None)?;
+        let dep_map = dep_map(&deps);
+
+        Ok(Project {
+            basepath,
+            fsroot,
+            dep_map,
+            deps,
+            config: solar_config,
+            single_file: Some(file.to_string()),
+        })
+    }
+
+    /// Paths of all solarfiles of this project,
+    /// along with the module they belong to.
+    /// Fails, if the files of the project can't be listed at all.
+    /// Files that can't be accessed are reported individually,
+    /// so the remaining files can still be read.
+    pub fn source_modules(&self) -> anyhow::Result<Vec<anyhow::Result<(String, IdPath)>>> {
+        let files = self
+            .source_files()?
+            .into_iter()
+            .map(|file| {
+                let (path, filepath) = file?;
+
+                let path = path
+                    .to_str()
+                    .with_context(|| format!("filename {} is not valid UTF-8", path.display()))?
+                    .to_string();
+
+                // absolute id path.
+                let mut idmodule = self.basepath.clone();
+                for part in filepath.iter() {
+                    let part = part
+                        .to_str()
+                        .with_context(|| format!("filename {path} is not valid UTF-8"))?;
+                    idmodule.push(part.to_string());
+                }
+                // remove filename from IDmodule.
+                idmodule.pop();
+
+                Ok((path, idmodule))
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Parses a solarfile of this project
+    /// and resolves its imports.
+    /// Parse errors are reported along with the line they occur in.
+    pub fn parse<'a>(&self, file_id: FileId, file: &'a SourceFile) -> anyhow::Result<FileInfo<'a>> {
+        let path = &file.path;
+
+        FileInfo::from_code(
+            file_id,
+            path.clone(),
+            &self.dep_map,
+            &self.basepath,
+            &file.text,
+            &file.public_lines,
+        )
+        // the error borrows from the source database, so it is rendered right away.
+        .map_err(|e| match e.span() {
+            Some(span) => anyhow::anyhow!(file.diagnostic(file_id, span, &e)),
+            None => anyhow::anyhow!("{path}: {e}"),
+        })
+    }
+
+    /// Lists all solar files of this project.
+    /// Returns the path of every file,
+    /// along with the path relative to the project root.
+    /// Files are selected by the include and exclude patterns of the project.
+    /// Nested directories with their own solar.yaml are separate projects and skipped.
+    /// Entries that can't be read are returned as errors.
+    fn source_files(&self) -> anyhow::Result<Vec<anyhow::Result<(PathBuf, PathBuf)>>> {
+        if let Some(file) = &self.single_file {
+            // the single file lives at the root of the project.
+            let path = PathBuf::from(file);
+            let filename = PathBuf::from(path.file_name().expect("script to be a file"));
+            return Ok(vec![Ok((path, filename))]);
+        }
+
+        let filter = SourceFilter::new(&self.fsroot, &self.config)?;
+        // We need to strip the path,
+        // because we don't care about the root file system
+        let relative = |path: &'_ Path| -> PathBuf {
+            path.strip_prefix(&self.fsroot)
+                .expect("to strip common prefix of filepath")
+                .to_path_buf()
+        };
+
+        let mut files = Vec::new();
+
+        // sorted, so that files (and their IdFile) don't depend on the order of the filesystem.
+        let walk = WalkDir::new(&self.fsroot)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                if entry.depth() == 0 || !entry.file_type().is_dir() {
+                    return true;
+                }
+
+                let nested_project = entry.path().join("solar.yaml").is_file();
+                !nested_project && filter.visit_dir(&relative(entry.path()))
+            });
+
+        for entry in walk {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // the error names the path it occured at.
+                    files.push(Err(e.into()));
+                    continue;
+                }
             };
 
             if !entry.file_type().is_file() {
                 continue;
             }
 
-            let filename = entry.file_name().to_str().expect("read filename");
-            if !filename.ends_with(".sol") {
+            let path = entry.path();
+            let filepath = relative(path);
+            if !filter.is_source(&filepath) {
                 continue;
             }
 
-            let path = entry.path();
-            // We need to strip the path,
-            // because we don't care about the root file system
-            let filepath = path
-                .strip_prefix(&self.fsroot)
-                .expect("to strip common prefix of filepath");
-
-            // absolute id path.
-            let mut idmodule = self
-                .basepath
-                .iter()
-                .cloned()
-                .chain(
-                    filepath
-                        .iter()
-                        .map(|f| f.to_str().expect("receive str from OsString").to_string()),
-                )
-                .collect::<Vec<_>>();
-            // remove filename from IDmodule.
-            idmodule.pop().unwrap();
-
-            // read in source code of file.
-            // and leak the memory.
-            // NOTE: for now we just keep all the sourcefiles in memory.
-            // maybe later we will do the opposite and never keep them in memory, but instead read them when needed only.
-            // This requires changes to the AST Nodes. All have a span: &str.
-            // We'd prefer to have a token_start: u32 on over node. (We don't REALLY need token_end)
-            // and remember for each Ast the file name.
-            let mut source_code = std::fs::read_to_string(path).expect("read solar file");
-            source_code.shrink_to_fit();
-            let content = leak_string(source_code);
-
-            let path = path.to_str().expect("read filename").to_string();
-            let context = format!("reading file {path}");
-            let fileinfo = FileInfo::from_code(path, &self.dep_map, &self.basepath, content)
-                .context(context)?;
-
-            map.entry(idmodule)
-                .or_insert(Module::new(project_id))
-                .add_file(fileinfo);
+            files.push(Ok((path.to_path_buf(), filepath)));
         }
 
-        Ok(map)
+        Ok(files)
     }
 }
 
-fn leak_string(s: String) -> &'static str {
-    let b = s.into_boxed_str();
-    let b = Box::leak(b);
-
-    unsafe { std::mem::transmute(b) }
+/// Mapping needed to resolve imports in a project to actual dependencies
+fn dep_map(deps: &[ResolvedDependency]) -> HashMap<String, IdPath> {
+    deps.iter()
+        .map(|d| {
+            let value = d.basepath();
+            let key = d.name.clone();
+            (key, value)
+        })
+        .collect()
 }
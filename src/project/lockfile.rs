@@ -0,0 +1,334 @@
+/// This file contains code
+/// for recording resolved dependencies in the solar.lock,
+/// so that resolution is reproducible across machines.
+use super::{ProjectInfo, ResolvedDependency, Source};
+use anyhow::{bail, Context};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Name of the lockfile, placed next to the solar.yaml
+pub const LOCKFILE: &str = "solar.lock";
+
+/// Structure of the solar.lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    /// All libraries the project depends on, directly or indirectly.
+    /// Sorted by name, publisher and version.
+    pub libraries: Vec<LockedLibrary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedLibrary {
+    pub name: String,
+    pub publisher: String,
+    pub version: String,
+    /// Where the library was taken from, e.g. `solarpath`.
+    /// Local directories are relative to the lockfile,
+    /// so that the lockfile is the same on every machine.
+    pub source: String,
+    /// sha256 of all files inside the library directory.
+    /// Libraries included via `path:` are not hashed,
+    /// as they are expected to change along with the project depending on them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl Lockfile {
+    /// Reads the lockfile inside the project directory `dir`.
+    /// Returns None, if there is no lockfile.
+    pub fn read(dir: &str) -> anyhow::Result<Option<Self>> {
+        let path = Path::new(dir).join(LOCKFILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let lock = serde_yaml::from_str(&content)
+            .with_context(|| format!("parsing {}", path.display()))?;
+
+        Ok(Some(lock))
+    }
+
+    /// Writes the lockfile into the project directory `dir`.
+    pub fn write(&self, dir: &str) -> anyhow::Result<()> {
+        let path = Path::new(dir).join(LOCKFILE);
+        let content = format!(
+            "# This file is generated by solar. Do not edit it by hand.\n{}",
+            serde_yaml::to_string(self)?
+        );
+
+        std::fs::write(&path, content).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Records all libraries that have been read in,
+    /// for one or more target projects (e.g. the members of a workspace).
+    /// `dir` is the directory the lockfile belongs in.
+    pub fn from_projects(dir: &str, targets: &[ProjectInfo]) -> anyhow::Result<Self> {
+        // every dependency, that has been loaded as a project.
+        let mut deps: BTreeMap<_, &ResolvedDependency> = BTreeMap::new();
+        for projects in targets {
+            for (_, project) in projects.iter_values() {
+                for dep in &project.deps {
+                    deps.insert(dep.basepath(), dep);
+                }
+            }
+        }
+
+        let libraries = deps
+            .into_values()
+            .map(|dep| {
+                let hash = match dep.source {
+                    Source::Path(_) => None,
+                    _ => Some(hash_dir(&dep.dir)?),
+                };
+
+                Ok(LockedLibrary {
+                    name: dep.name.clone(),
+                    publisher: dep.publisher.clone(),
+                    version: dep.version.clone(),
+                    source: locked_source(&dep.source, dir),
+                    hash,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Lockfile { libraries })
+    }
+
+    /// Finds the highest locked version of a library matching the requirement.
+    pub fn locked_version(&self, name: &str, publisher: &str, req: &VersionReq) -> Option<Version> {
+        self.libraries
+            .iter()
+            .filter(|l| l.name == name && l.publisher == publisher)
+            .filter_map(|l| Version::parse(&l.version).ok())
+            .filter(|v| req.matches(v))
+            .max()
+    }
+
+    /// Fails with a description of all differences,
+    /// if the `resolved` libraries do not match this lockfile.
+    pub fn verify(&self, resolved: &Lockfile) -> anyhow::Result<()> {
+        let key = |l: &LockedLibrary| format!("{}({}) {}", l.name, l.publisher, l.version);
+
+        let locked = self
+            .libraries
+            .iter()
+            .map(|l| (key(l), l))
+            .collect::<BTreeMap<_, _>>();
+        let current = resolved
+            .libraries
+            .iter()
+            .map(|l| (key(l), l))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut differences = Vec::new();
+
+        for (key, lib) in &current {
+            match locked.get(key) {
+                None => differences.push(format!("{key} is not in {LOCKFILE}")),
+                Some(l) if l.source != lib.source => differences.push(format!(
+                    "{key} was locked from {}, but resolved from {}",
+                    l.source, lib.source
+                )),
+                Some(l) if l.hash != lib.hash => {
+                    differences.push(format!("{key} has changed since it was locked"))
+                }
+                Some(_) => {}
+            }
+        }
+
+        for key in locked.keys() {
+            if !current.contains_key(key) {
+                differences.push(format!("{key} is locked, but no longer resolved"));
+            }
+        }
+
+        if !differences.is_empty() {
+            bail!(
+                "dependencies do not match {LOCKFILE}:\n  {}",
+                differences.join("\n  ")
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// How the source of a library is recorded in the lockfile inside `dir`.
+/// Local directories are written relative to `dir`,
+/// while the canonical path only identifies the library internally.
+fn locked_source(source: &Source, dir: &str) -> String {
+    match source {
+        Source::Path(path) => format!("path+{}", relative_to(dir, path)),
+        Source::Git {
+            url,
+            commit,
+            relative: true,
+        } => format!("git+{}#{commit}", relative_to(dir, url)),
+        source => source.to_string(),
+    }
+}
+
+/// Path of `path` relative to the directory `dir`, e.g. `../libs/util`.
+/// Both are compared in their canonical form.
+/// The path is returned unchanged, if `dir` can't be found.
+fn relative_to(dir: &str, path: &str) -> String {
+    let Ok(dir) = Path::new(dir).canonicalize() else {
+        return path.to_string();
+    };
+
+    let dir = dir.components().collect::<Vec<_>>();
+    let path = Path::new(path).components().collect::<Vec<_>>();
+    let common = dir.iter().zip(&path).take_while(|(a, b)| a == b).count();
+
+    let relative = std::iter::repeat_n(Component::ParentDir, dir.len() - common)
+        .chain(path[common..].iter().copied())
+        .collect::<PathBuf>();
+
+    if relative.as_os_str().is_empty() {
+        return ".".to_string();
+    }
+
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Hashes the contents of all files in a directory,
+/// along with their paths relative to the directory.
+/// Files are visited in a fixed order, so the hash doesn't depend on the filesystem.
+fn hash_dir(dir: &str) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("walking directory {dir}"))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let relative = path
+            .strip_prefix(dir)
+            .expect("to strip common prefix of filepath");
+        let relative = relative
+            .iter()
+            .map(|c| c.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let content = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    Ok(format!("sha256:{hash}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(name: &str, version: &str, source: &str, hash: Option<&str>) -> LockedLibrary {
+        LockedLibrary {
+            name: name.to_string(),
+            publisher: "acme".to_string(),
+            version: version.to_string(),
+            source: source.to_string(),
+            hash: hash.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn locked_version_picks_highest_match() {
+        let lock = Lockfile {
+            libraries: vec![
+                library("util", "0.1.0", "solarpath", Some("sha256:a")),
+                library("util", "0.1.3", "solarpath", Some("sha256:b")),
+                library("util", "0.2.0", "solarpath", Some("sha256:c")),
+                library("other", "0.1.9", "solarpath", Some("sha256:d")),
+            ],
+        };
+
+        let req = VersionReq::parse("^0.1").unwrap();
+        assert_eq!(
+            lock.locked_version("util", "acme", &req),
+            Some(Version::new(0, 1, 3))
+        );
+
+        let req = VersionReq::parse("^0.3").unwrap();
+        assert_eq!(lock.locked_version("util", "acme", &req), None);
+        assert_eq!(
+            lock.locked_version("util", "other", &VersionReq::STAR),
+            None
+        );
+    }
+
+    #[test]
+    fn verify_accepts_same_libraries() {
+        let lock = Lockfile {
+            libraries: vec![
+                library("util", "0.1.0", "solarpath", Some("sha256:a")),
+                library("local", "0.1.0", "path+../local", None),
+            ],
+        };
+
+        assert!(lock.verify(&lock.clone()).is_ok());
+    }
+
+    #[test]
+    fn verify_reports_all_differences() {
+        let lock = Lockfile {
+            libraries: vec![
+                library("changed", "0.1.0", "solarpath", Some("sha256:a")),
+                library("moved", "0.1.0", "path+../moved", None),
+                library("removed", "0.1.0", "solarpath", Some("sha256:b")),
+            ],
+        };
+        let resolved = Lockfile {
+            libraries: vec![
+                library("changed", "0.1.0", "solarpath", Some("sha256:c")),
+                library("moved", "0.1.0", "path+../libs/moved", None),
+                library("added", "0.1.0", "solarpath", Some("sha256:d")),
+            ],
+        };
+
+        let message = lock.verify(&resolved).unwrap_err().to_string();
+        assert!(message.contains("added(acme) 0.1.0 is not in solar.lock"));
+        assert!(message.contains("changed(acme) 0.1.0 has changed since it was locked"));
+        assert!(message.contains(
+            "moved(acme) 0.1.0 was locked from path+../moved, but resolved from path+../libs/moved"
+        ));
+        assert!(message.contains("removed(acme) 0.1.0 is locked, but no longer resolved"));
+    }
+
+    #[test]
+    fn relative_to_lockfile_dir() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let dir_str = dir.to_str().unwrap();
+
+        let sibling = dir.parent().unwrap().join("libs").join("util");
+        assert_eq!(
+            relative_to(dir_str, sibling.to_str().unwrap()),
+            "../libs/util"
+        );
+        assert_eq!(
+            relative_to(dir_str, dir.join("util").to_str().unwrap()),
+            "util"
+        );
+        assert_eq!(relative_to(dir_str, dir_str), ".");
+    }
+}
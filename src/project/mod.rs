@@ -1,36 +1,168 @@
+mod git;
+mod graph;
+mod imports;
 mod libraries;
+mod lockfile;
 mod modules;
 mod project_info;
+mod scaffold;
+mod source_db;
+mod sources;
+mod validate;
+mod workspace;
+pub use git::*;
+pub use graph::*;
+pub use imports::*;
 pub use libraries::*;
+pub use lockfile::*;
 pub use modules::*;
 pub use project_info::*;
+pub use scaffold::*;
+pub use source_db::*;
+pub use sources::*;
+pub use validate::*;
+pub use workspace::*;
 
 use crate::{util::IdPath, Config};
+use anyhow::{bail, Context};
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
 /// Structure of the solar.yaml file
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SolarConfig {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub publisher: Option<String>,
     pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     authors: Option<Vec<String>>,
 
-    dependencies: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependencies: Option<HashMap<String, DependencySpec>>,
+
+    /// Patterns of source files to compile. Defaults to all .sol files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    /// Patterns of files and directories to skip, in addition to the .solarignore.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+}
+
+/// Value of an entry in the dependencies of a solar.yaml
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum DependencySpec {
+    /// Version requirement, e.g. `^0.1.0`.
+    /// May be prefixed with a git repo, e.g. `github.com/solar-lang/std@0.1.0`,
+    /// in which case the version names a tag or commit.
+    Version(String),
+    /// Library in the local filesystem, e.g. `{ path: ../mylib }`
+    Path {
+        /// Relative to the root of the declaring project
+        path: String,
+    },
+}
+
+// not derived, because untagged enums only report
+// that the value didn't match any variant.
+impl<'de> Deserialize<'de> for DependencySpec {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DependencySpecVisitor)
+    }
+}
+
+struct DependencySpecVisitor;
+
+impl<'de> serde::de::Visitor<'de> for DependencySpecVisitor {
+    type Value = DependencySpec;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a version requirement or `{ path: … }`")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(DependencySpec::Version(v.to_string()))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let entry = map.next_entry::<String, serde_yaml::Value>()?;
+        let extra = map.next_key::<serde::de::IgnoredAny>()?;
+
+        match (entry, extra) {
+            (Some((key, serde_yaml::Value::String(path))), None) if key == "path" => {
+                Ok(DependencySpec::Path { path })
+            }
+            _ => Err(serde::de::Error::custom(
+                "expected a version requirement or `{ path: … }`",
+            )),
+        }
+    }
 }
 
+/// Name of the standard library
+pub const STD_NAME: &str = "std";
+/// Publisher of the standard library
+pub const STD_PUBLISHER: &str = "solar-lang";
+
 impl SolarConfig {
+    /// Reads and validates a solar.yaml file.
     pub fn read(path: &str) -> anyhow::Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let cfg: Self = serde_yaml::from_str(&content)?;
+        let cfg = parse_config(&content, path)?;
 
         Ok(cfg)
     }
 
+    /// Dependencies declared in the config,
+    /// resolved to concrete libraries in the filesystem.
+    /// `fsroot` is the root of the project declaring the dependencies.
+    pub fn resolve_deps(
+        &self,
+        fsroot: &str,
+        config: &Config,
+        lock: Option<&Lockfile>,
+    ) -> anyhow::Result<Vec<ResolvedDependency>> {
+        let mut deps = self
+            .deps()
+            .iter()
+            .map(|d| d.resolve(fsroot, config, lock))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // keep the order of dependencies independent of the HashMap
+        deps.sort_by_key(ResolvedDependency::basepath);
+        Ok(deps)
+    }
+
+    /// Config of projects without a solar.yaml.
+    /// Depends on the most recent std library installed in the solarpath, if any.
+    pub fn implicit(name: &str, solarpath: &[String]) -> Self {
+        let dependencies = latest_installed(solarpath, STD_NAME, STD_PUBLISHER).map(|version| {
+            let key = format!("{STD_NAME}({STD_PUBLISHER})");
+            HashMap::from([(key, DependencySpec::Version(version))])
+        });
+
+        SolarConfig {
+            name: name.to_string(),
+            publisher: None,
+            version: "0.0.0".to_string(),
+            description: None,
+            author: None,
+            authors: None,
+            dependencies,
+            include: None,
+            exclude: None,
+        }
+    }
+
     /*
         pub fn basepath(&self) -> IdPath {
             let publisher = if let Some(p) = &self.publisher { p } else { "" };
@@ -57,47 +189,321 @@ impl SolarConfig {
         }
     */
 
+    /// Dependencies declared in the config, not yet resolved.
+    /// The dependency keys have been validated when reading the config.
     pub fn deps(&self) -> Vec<Dependency> {
         let Some(deps) = &self.dependencies else {
             return Vec::new();
         };
 
         deps.iter()
-            .map(|(key, value)| Dependency::from_key_value(key, value).expect("read dependencies"))
+            .map(|(key, value)| {
+                Dependency::from_key_value(key, value)
+                    .expect("dependencies to be validated when reading solar.yaml")
+            })
             .collect()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dependency {
     pub name: String,
     pub publisher: String,
-    pub version: String,
+    /// Version requirement, e.g. `^0.1.0` or `>=0.1, <0.3`.
+    /// A plain version like `0.1.0` is treated as `^0.1.0`.
+    /// Path dependencies accept any version (`*`).
+    /// For git dependencies this is the tag or commit to check out.
+    pub requirement: String,
+    /// Git repository to fetch the library from
     pub repo: Option<String>,
+    /// Directory of the library, relative to the declaring project
+    pub path: Option<String>,
 }
 
-impl Dependency {
+/// A dependency resolved to a concrete library within the local filesystem.
+#[derive(Debug, Clone)]
+pub struct ResolvedDependency {
+    pub name: String,
+    pub publisher: String,
+    /// Exact version of the library
+    pub version: String,
+    /// Root directory of the library
+    pub dir: String,
+    pub source: Source,
+    /// Other roots of the solarpath, that contain the same version of the library,
+    /// but come after the root the library has been taken from.
+    pub also_found_in: Vec<String>,
+}
+
+/// Where a library has been found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Installed inside the libraries directory of the solarpath
+    SolarPath,
+    /// Local directory, declared via `path:`. Holds the canonical path.
+    Path(String),
+    /// Commit of a git repository, checked out into the solarpath
+    Git {
+        url: String,
+        commit: String,
+        /// Whether the repository has been given relative to the declaring project,
+        /// e.g. `./vendor/lib`. The url holds the canonical path then.
+        relative: bool,
+    },
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::SolarPath => write!(f, "solarpath"),
+            Source::Path(dir) => write!(f, "path+{dir}"),
+            Source::Git { url, commit, .. } => write!(f, "git+{url}#{commit}"),
+        }
+    }
+}
+
+impl ResolvedDependency {
     pub fn basepath(&self) -> IdPath {
         let name = &self.name;
         let publisher = &self.publisher;
         let version = &self.version;
 
-        vec![format!("{name}({publisher})"), version.to_owned()]
+        match &self.source {
+            Source::SolarPath => vec![format!("{name}({publisher})"), version.to_owned()],
+            // libraries from the filesystem are identified by their location,
+            // so they never collide with installed versions of the same library.
+            Source::Path(dir) => vec![format!("{name}({publisher})"), format!("path:{dir}")],
+            Source::Git { commit, .. } => {
+                vec![format!("{name}({publisher})"), format!("git:{commit}")]
+            }
+        }
+    }
+}
+
+/// Directory containing all versions of a library installed in one root of the solarpath
+fn library_dir(root: &str, name: &str, publisher: &str) -> String {
+    let mut path = root.to_string();
+    if !path.ends_with('/') {
+        path.push('/');
     }
 
-    /// Gives the directory of the library within the local filesystem.
-    pub fn dir(&self, config: &Config) -> String {
-        let mut path = config.solarpath.clone();
-        if !path.ends_with('/') {
-            path.push('/');
+    format!("{path}libraries/{name}({publisher})")
+}
+
+/// All versions of a library installed in one root of the solarpath, in ascending order.
+/// Directories, that aren't named after a semantic version, are ignored.
+fn installed_in(root: &str, name: &str, publisher: &str) -> Vec<Version> {
+    let Ok(entries) = std::fs::read_dir(library_dir(root, name, publisher)) else {
+        return Vec::new();
+    };
+
+    let mut versions = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|version| Version::parse(&version).ok())
+        .collect::<Vec<_>>();
+
+    versions.sort();
+    versions
+}
+
+/// All versions of a library installed in any root of the solarpath, in ascending order.
+pub fn installed_versions(solarpath: &[String], name: &str, publisher: &str) -> Vec<Version> {
+    let mut versions = solarpath
+        .iter()
+        .flat_map(|root| installed_in(root, name, publisher))
+        .collect::<Vec<_>>();
+
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// Finds the highest version of a library installed in the solarpath.
+pub fn latest_installed(solarpath: &[String], name: &str, publisher: &str) -> Option<String> {
+    installed_versions(solarpath, name, publisher)
+        .pop()
+        .map(|v| v.to_string())
+}
+
+impl Dependency {
+    /// Resolves the version requirement to the highest matching library
+    /// installed in the solarpath.
+    /// The roots of the solarpath are searched in order,
+    /// the first root containing a matching version supplies the library.
+    /// Versions recorded in the lockfile are preferred, as long as they are installed.
+    /// Path dependencies are resolved relative to `fsroot`,
+    /// the root of the declaring project.
+    /// Git dependencies are checked out into the solarpath.
+    pub fn resolve(
+        &self,
+        fsroot: &str,
+        config: &Config,
+        lock: Option<&Lockfile>,
+    ) -> anyhow::Result<ResolvedDependency> {
+        if let Some(path) = &self.path {
+            return self.resolve_path(fsroot, path);
+        }
+
+        if let Some(repo) = &self.repo {
+            // local repositories may be given relative to the declaring project.
+            let repo_is_relative = repo.starts_with('.');
+            let repo = if repo_is_relative {
+                let dir = Path::new(fsroot).join(repo);
+                let dir = dir
+                    .canonicalize()
+                    .with_context(|| format!("finding repository {}", dir.display()))?;
+                dir.to_str().context("read path of repository")?.to_string()
+            } else {
+                repo.clone()
+            };
+
+            return self.resolve_git(config.cache_root(), &repo, repo_is_relative);
         }
 
-        path.push_str("libraries/");
+        let Dependency {
+            name,
+            publisher,
+            requirement,
+            ..
+        } = self;
+
+        let req = VersionReq::parse(requirement).with_context(|| {
+            format!("parsing version requirement '{requirement}' of {name}({publisher})")
+        })?;
+
+        let roots = config
+            .solarpath
+            .iter()
+            .map(|root| (root, installed_in(root, name, publisher)))
+            .collect::<Vec<_>>();
+        let available = installed_versions(&config.solarpath, name, publisher);
+
+        let locked = lock
+            .and_then(|lock| lock.locked_version(name, publisher, &req))
+            .filter(|v| available.contains(v));
+
+        let found = match locked {
+            Some(version) => roots
+                .iter()
+                .find(|(_, versions)| versions.contains(&version))
+                .map(|(root, _)| (*root, version)),
+            None => roots.iter().find_map(|(root, versions)| {
+                let version = versions.iter().rev().find(|v| req.matches(v))?;
+                Some((*root, version.clone()))
+            }),
+        };
+
+        let Some((root, version)) = found else {
+            if available.is_empty() {
+                let roots = config.solarpath.join(", ");
+                bail!("no version of {name}({publisher}) installed in any of {roots}");
+            }
+
+            let available = available
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!("no installed version of {name}({publisher}) matches '{requirement}'. Available versions: {available}");
+        };
+
+        let also_found_in = roots
+            .iter()
+            .skip_while(|(r, _)| *r != root)
+            .skip(1)
+            .filter(|(_, versions)| versions.contains(&version))
+            .map(|(r, _)| r.to_string())
+            .collect();
+
+        Ok(ResolvedDependency {
+            name: name.clone(),
+            publisher: publisher.clone(),
+            version: version.to_string(),
+            dir: format!("{}/{version}", library_dir(root, name, publisher)),
+            source: Source::SolarPath,
+            also_found_in,
+        })
+    }
+
+    /// Resolves a dependency on a library in the local filesystem.
+    fn resolve_path(&self, fsroot: &str, path: &str) -> anyhow::Result<ResolvedDependency> {
+        let Dependency {
+            name, publisher, ..
+        } = self;
+
+        let dir = Path::new(fsroot).join(path);
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("finding {name}({publisher}) at {}", dir.display()))?;
+        let dir = dir.to_str().context("read path of library")?.to_string();
+
+        let lib = self.read_library(&dir)?;
+
+        Ok(ResolvedDependency {
+            name: name.clone(),
+            publisher: publisher.clone(),
+            version: lib.version,
+            dir: dir.clone(),
+            source: Source::Path(dir),
+            also_found_in: Vec::new(),
+        })
+    }
+
+    /// Resolves a dependency on a tag or commit of a git repository.
+    /// `relative` tells, whether the repository has been given relative to the declaring project.
+    fn resolve_git(
+        &self,
+        solarpath: &str,
+        repo: &str,
+        relative: bool,
+    ) -> anyhow::Result<ResolvedDependency> {
+        let Dependency {
+            name,
+            publisher,
+            requirement,
+            ..
+        } = self;
+
+        let GitCheckout { url, commit, dir } = checkout(solarpath, repo, requirement)
+            .with_context(|| format!("fetching {name}({publisher}) from {repo}"))?;
+
+        let lib = self.read_library(&dir)?;
+
+        Ok(ResolvedDependency {
+            name: name.clone(),
+            publisher: publisher.clone(),
+            version: lib.version,
+            dir,
+            source: Source::Git {
+                url,
+                commit,
+                relative,
+            },
+            also_found_in: Vec::new(),
+        })
+    }
+
+    /// Reads the solar.yaml of a library outside of the solarpath,
+    /// making sure it is the library that has been asked for.
+    fn read_library(&self, dir: &str) -> anyhow::Result<SolarConfig> {
+        let Dependency {
+            name, publisher, ..
+        } = self;
 
-        path + &self.basepath().join("/")
+        let lib = SolarConfig::read(&format!("{dir}/solar.yaml"))
+            .with_context(|| format!("reading solar.yaml of {name}({publisher}) at {dir}"))?;
+        if &lib.name != name || lib.publisher.as_ref().is_some_and(|p| p != publisher) {
+            bail!(
+                "expected library {name}({publisher}) at {dir}, but found {}({})",
+                lib.name,
+                lib.publisher.as_deref().unwrap_or_default()
+            );
+        }
+
+        Ok(lib)
     }
 
-    fn from_key_value(key: &str, value: &str) -> Result<Self, String> {
+    fn from_key_value(key: &str, value: &DependencySpec) -> Result<Self, String> {
         let Some((name, rest)) = key.split_once('(') else {
             return Err(format!(
                 "expect dependency keys to adhere to pattern '<name>(<publisher>)', but haven't found '(' in key '{key}'",
@@ -112,21 +518,124 @@ impl Dependency {
         let publisher = rest[..rest.len() - 1].to_string();
         let name = name.to_string();
 
-        // the version may be written after declaration of a git repo.
-        let (repo, version) = if let Some((repo, version)) = value.rsplit_once('@') {
-            let repo = Some(repo.to_string());
-            let version = version.to_string();
+        let value = match value {
+            DependencySpec::Version(value) => value,
+            DependencySpec::Path { path } => {
+                return Ok(Self {
+                    name,
+                    publisher,
+                    requirement: "*".to_string(),
+                    repo: None,
+                    path: Some(path.clone()),
+                })
+            }
+        };
 
-            (repo, version)
-        } else {
-            (None, value.to_string())
+        // the version may be written after declaration of a git repo.
+        // Only an `@` after the last `/` or `:` separates it,
+        // as the repo may contain one itself, e.g. `git@github.com:acme/lib`.
+        let start = value.rfind(['/', ':']).map_or(0, |i| i + 1);
+        let (repo, requirement) = match value[start..].rfind('@') {
+            Some(at) => {
+                let (repo, version) = value.split_at(start + at);
+                (Some(repo.to_string()), version[1..].to_string())
+            }
+            // the missing version is reported when validating
+            None if start > 0 => (Some(value.to_string()), String::new()),
+            None => (None, value.to_string()),
         };
 
         Ok(Self {
             name,
             publisher,
-            version,
+            requirement,
             repo,
+            path: None,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(value: &str) -> DependencySpec {
+        DependencySpec::Version(value.to_string())
+    }
+
+    #[test]
+    fn dependency_with_version_requirement() {
+        let dep = Dependency::from_key_value("std(solar-lang)", &version(">=0.1, <0.3")).unwrap();
+
+        assert_eq!(dep.name, "std");
+        assert_eq!(dep.publisher, "solar-lang");
+        assert_eq!(dep.requirement, ">=0.1, <0.3");
+        assert_eq!(dep.repo, None);
+        assert_eq!(dep.path, None);
+    }
+
+    #[test]
+    fn dependency_key_without_publisher() {
+        let e = Dependency::from_key_value("std", &version("0.1.0")).unwrap_err();
+        assert!(e.contains("haven't found '('"));
+
+        let e = Dependency::from_key_value("std(solar-lang", &version("0.1.0")).unwrap_err();
+        assert!(e.contains("haven't found ')'"));
+    }
+
+    #[test]
+    fn dependency_with_path() {
+        let spec = DependencySpec::Path {
+            path: "../util".to_string(),
+        };
+        let dep = Dependency::from_key_value("util(acme)", &spec).unwrap();
+
+        assert_eq!(dep.requirement, "*");
+        assert_eq!(dep.repo, None);
+        assert_eq!(dep.path.as_deref(), Some("../util"));
+    }
+
+    #[test]
+    fn dependency_with_git_repo() {
+        let cases = [
+            (
+                "github.com/solar-lang/std@0.1.0",
+                "github.com/solar-lang/std",
+                "0.1.0",
+            ),
+            (
+                "git@github.com:acme/lib@v1.2",
+                "git@github.com:acme/lib",
+                "v1.2",
+            ),
+            ("../mylib@3f2a9c1", "../mylib", "3f2a9c1"),
+            // the revision is missing, which is reported when validating
+            ("git@github.com:acme/lib", "git@github.com:acme/lib", ""),
+            ("github.com/acme/lib", "github.com/acme/lib", ""),
+        ];
+
+        for (value, repo, requirement) in cases {
+            let dep = Dependency::from_key_value("lib(acme)", &version(value)).unwrap();
+            assert_eq!(dep.repo.as_deref(), Some(repo), "repo of {value}");
+            assert_eq!(dep.requirement, requirement, "revision of {value}");
+        }
+    }
+
+    #[test]
+    fn deserialize_dependency_spec() {
+        let spec: DependencySpec = serde_yaml::from_str("^0.1.0").unwrap();
+        assert!(matches!(spec, DependencySpec::Version(v) if v == "^0.1.0"));
+
+        let spec: DependencySpec = serde_yaml::from_str("{ path: ../util }").unwrap();
+        assert!(matches!(spec, DependencySpec::Path { path } if path == "../util"));
+
+        for yaml in ["{ paht: ../util }", "{ path: ../util, version: 1 }", "[1]"] {
+            let e = serde_yaml::from_str::<DependencySpec>(yaml).unwrap_err();
+            assert!(
+                e.to_string()
+                    .contains("expected a version requirement or `{ path: … }`"),
+                "{yaml}: {e}"
+            );
+        }
+    }
+}
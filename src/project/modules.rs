@@ -1,5 +1,7 @@
+use super::{line_column, offset_in, FileId};
 use crate::id::{IdItem, SymbolId};
 use crate::util::IdPath;
+use nom::error::VerboseErrorKind;
 use solar_parser::ast::import::Selection;
 use solar_parser::{ast, Ast};
 use std::collections::HashMap;
@@ -12,8 +14,14 @@ pub struct Module<'a> {
     // NOTE u32 might be better
     // TODO we don't need that, after having resolved all import tables at ast creation time.
     pub project_id: usize,
+    /// Path of this module, e.g. std(solar-lang)/0.0.1/types
+    pub idmodule: IdPath,
     /// Set of all file inside this module
     pub files: Vec<FileInfo<'a>>,
+    /// Every symbol declared inside this module (across all files),
+    /// e.g. `length` => [length(String), length(Array)]
+    /// Built up while adding files, so looking up a symbol doesn't need to scan the ASTs.
+    pub symbols: HashMap<String, Vec<SymbolId>>,
     // compiled_functions on module level, because
     //     1.) we need file distinction only for resolving imports
     //     2.) we have a flat hierarchy inside a module.
@@ -21,91 +29,165 @@ pub struct Module<'a> {
 }
 
 impl<'a> Module<'a> {
-    pub fn new(project_id: usize) -> Self {
+    pub fn new(project_id: usize, idmodule: IdPath) -> Self {
         Self {
             project_id,
+            idmodule,
             files: Vec::new(),
+            symbols: HashMap::new(),
         }
     }
 
+    /// Adds a file to the module,
+    /// along with all symbols it declares.
     pub fn add_file(&mut self, file: FileInfo<'a>) {
+        let idfile = self.files.len() as u16;
+
+        for (iditem, i) in file.ast.items.iter().enumerate() {
+            let iditem = iditem as u16;
+
+            match i {
+                ast::body::BodyItem::Function(f) => {
+                    self.add_symbol(f.name.value, idfile, IdItem::Func(iditem));
+                }
+                ast::body::BodyItem::TypeDecl(t) => {
+                    // e.g. type A
+                    self.add_symbol(t.name.value, idfile, IdItem::Type(iditem));
+
+                    // fields become functions e.g. A.a
+                    let fields = match &t.fields {
+                        // E.g. type Maybe a = Some a | None
+                        // makes `Some` become a function
+                        // and None:(a) constant
+                        ast::EnumOrStructFields::EnumFields(fields) => fields,
+                        // E.g. type Wrapper a
+                        //      -   value: a
+                        // derives `value(Wrapper) -> a` as a function
+                        ast::EnumOrStructFields::StructFields(fields) => fields,
+                    };
+
+                    for (idfield, f) in fields.iter().enumerate() {
+                        let idfield = idfield as u16;
+                        self.add_symbol(f.name.value, idfile, IdItem::Method(iditem, idfield));
+                    }
+                }
+                ast::body::BodyItem::Let(l) => {
+                    self.add_symbol(l.identifier.value, idfile, IdItem::GlobalVar(iditem));
+                }
+                ast::body::BodyItem::BuildinTypeDecl(t) => {
+                    // the type itself is linked separately,
+                    // but its name can still be imported, e.g. `use @std.types.int.Int`
+                    self.add_symbol(t.name.value, idfile, IdItem::Type(iditem));
+                }
+
+                // Tests don't have names.
+                _ => continue,
+            }
+        }
+
         self.files.push(file);
     }
 
-    pub fn find(&'a self, symbol: &str, idmodule: &[String]) -> Result<Vec<SymbolId>, FindError> {
-        let mut v = Vec::new();
-
-        for (idfile, fileinfo) in self.files.iter().enumerate() {
-            let idfile = idfile as u16;
-            let ast = &fileinfo.ast;
-            for (iditem, i) in ast.items.iter().enumerate() {
-                let iditem = iditem as u16;
+    fn add_symbol(&mut self, name: &str, idfile: u16, iditem: IdItem) {
+        let symbol_id = (self.idmodule.clone(), idfile, iditem);
+        self.symbols
+            .entry(name.to_string())
+            .or_default()
+            .push(symbol_id);
+    }
 
-                match i {
-                    ast::body::BodyItem::Function(f) if f.name == symbol => {
-                        v.push((idmodule.to_vec(), idfile, IdItem::Func(iditem)));
-                    }
-                    ast::body::BodyItem::TypeDecl(t) => {
-                        // e.g. type A
-                        if t.name == symbol {
-                            v.push((idmodule.to_vec(), idfile, IdItem::Type(iditem)));
-                        }
-
-                        // fields become functions e.g. A.a
-                        match &t.fields {
-                            ast::EnumOrStructFields::EnumFields(fields) => {
-                                // E.g. type Maybe a = Some a | None
-                                // makes `Some` become a function
-                                // and None:(a) constant
-
-                                for (idfield, f) in fields.iter().enumerate() {
-                                    let idfield = idfield as u16;
-
-                                    if f.name == symbol {
-                                        let sid = (
-                                            idmodule.to_vec(),
-                                            idfile,
-                                            IdItem::Method(iditem, idfield),
-                                        );
-                                        v.push(sid);
-                                    }
-                                }
-                            }
-                            ast::EnumOrStructFields::StructFields(fields) => {
-                                // E.g. type Wrapper a
-                                //      -   value: a
-                                // derives `value(Wrapper) -> a` as a function
-
-                                for (idfield, f) in fields.iter().enumerate() {
-                                    let idfield = idfield as u16;
-
-                                    if f.name == symbol {
-                                        let sid = (
-                                            idmodule.to_vec(),
-                                            idfile,
-                                            IdItem::Method(iditem, idfield),
-                                        );
-                                        v.push(sid);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    ast::body::BodyItem::Let(l) if l.identifier == symbol => {
-                        v.push((idmodule.to_vec(), idfile, IdItem::GlobalVar(iditem)));
-                    }
+    /// All symbols declared with the name `symbol` inside this module.
+    pub fn find(&self, symbol: &str) -> Result<&[SymbolId], FindError> {
+        match self.symbols.get(symbol) {
+            Some(symbols) => Ok(symbols),
+            None => Err(FindError::NotFound(symbol.to_string())),
+        }
+    }
 
-                    _ => continue,
-                    // Tests don't have names,
+    /// Symbols, that are declared more than once inside this module.
+    /// Returns pairs of (first declaration, duplicate), along with the name.
+    ///
+    /// Functions may be overloaded, so they only clash,
+    /// if they take the same number of arguments with the same type annotations.
+    /// Types and global variables may not share their name with any other type or variable.
+    /// Derived methods are never reported, as they are overloaded by their type.
+    pub fn duplicates(&self) -> Vec<(&str, &SymbolId, &SymbolId)> {
+        let mut duplicates = Vec::new();
+
+        for (name, symbols) in &self.symbols {
+            for (i, symbol) in symbols.iter().enumerate() {
+                let Some(signature) = self.signature(symbol) else {
+                    continue;
+                };
+
+                let first = symbols[..i]
+                    .iter()
+                    .find(|other| self.signature(other).as_ref() == Some(&signature));
+
+                if let Some(first) = first {
+                    duplicates.push((name.as_str(), first, symbol));
                 }
             }
         }
 
-        if v.is_empty() {
-            return Err(FindError::NotFound(symbol.to_string()));
+        // report sorted by file and item, not in the order of the hashmap.
+        duplicates.sort_by(|a, b| a.2.cmp(b.2));
+        duplicates
+    }
+
+    /// Returns None for derived methods, which never clash.
+    fn signature(&self, (_, idfile, iditem): &SymbolId) -> Option<Signature<'_>> {
+        let items = &self.files[*idfile as usize].ast.items;
+
+        match iditem {
+            IdItem::Func(i) => {
+                let ast::body::BodyItem::Function(f) = &items[*i as usize] else {
+                    return None;
+                };
+
+                let args = f
+                    .args
+                    .iter()
+                    .map(|(_, ty)| ty.as_ref().map(|ty| ty.span.trim()))
+                    .collect();
+                Some(Signature::Function(args))
+            }
+            IdItem::Type(_) | IdItem::GlobalVar(_) => Some(Signature::Unique),
+            IdItem::Method(_, _) => None,
+        }
+    }
+
+    /// Whether a symbol of this module can be used from within the project `project_id`.
+    /// Items are visible everywhere inside their own project,
+    /// other projects only see items declared `pub`.
+    pub fn is_visible_from(&self, (_, idfile, iditem): &SymbolId, project_id: usize) -> bool {
+        if self.project_id == project_id {
+            return true;
         }
 
-        Ok(v)
+        self.files[*idfile as usize].is_public(item_index(iditem))
+    }
+
+    /// Where a symbol of this module is declared, e.g. `std/io/console.sol:12:9`
+    pub fn declaration(&self, (_, idfile, iditem): &SymbolId) -> String {
+        self.files[*idfile as usize].declaration(item_index(iditem))
+    }
+}
+
+/// What a symbol needs to differ in from other symbols of the same name.
+#[derive(Debug, PartialEq)]
+enum Signature<'s> {
+    /// Functions are distinguished by the type annotations of their arguments
+    Function(Vec<Option<&'s str>>),
+    /// Types and global variables may not share their name
+    Unique,
+}
+
+/// Item of the AST a symbol is declared by.
+/// Derived methods belong to the type declaring them.
+fn item_index(item: &IdItem) -> u16 {
+    match item {
+        IdItem::GlobalVar(i) | IdItem::Func(i) | IdItem::Type(i) | IdItem::Method(i, _) => *i,
     }
 }
 
@@ -133,6 +215,9 @@ impl std::fmt::Display for FindError {
 pub struct FileInfo<'a> {
     // NOTE this might be redundant
     pub filename: String,
+    /// Id of the file in the source database,
+    /// which the AST borrows from.
+    pub file_id: FileId,
 
     /// Maps individual symbols (e.g. `length`) to paths,
     /// where they should be found in (e.g. std/0.0.1/string/).
@@ -143,9 +228,33 @@ pub struct FileInfo<'a> {
     /// is valid, expected
     /// and will require resolving from multiple locations.
     pub imports: SymbolResolver,
+    /// Modules, all of whose symbols are imported, e.g.
+    ///    use @std.string..
+    /// Symbols of these modules are only considered,
+    /// if neither the current module nor an explicit import provides a symbol.
+    pub glob_imports: Vec<IdPath>,
+    /// Every import of the file, as written in the source code.
+    /// Used to check imports and report them, not for resolving symbols.
+    pub import_items: Vec<ImportItem<'a>>,
+    /// Source code of the file, which the AST borrows from.
+    pub source: &'a str,
+    /// Lines (starting at 1) of items, that have been declared `pub`
+    pub public_lines: &'a [usize],
     pub ast: Ast<'a>,
 }
 
+/// A single imported symbol, e.g. `length` in
+///    use @std.string.(length, concat)
+#[derive(Debug)]
+pub struct ImportItem<'a> {
+    /// None for wildcard imports, which import the module as a whole.
+    pub symbol: Option<String>,
+    /// Module the symbol is imported from
+    pub module: IdPath,
+    /// Name of the symbol in the source code (or the module, for wildcard imports)
+    pub span: &'a str,
+}
+
 #[derive(Debug, Error)]
 pub enum ResolveError<'a> {
     LibNotInDeps {
@@ -169,30 +278,122 @@ impl std::fmt::Display for ResolveError<'_> {
                 f,
                 "imported libraries '{libname}' not found in dependencies"
             ),
-            ResolveError::ParseErr(e) => e.fmt(f),
+            ResolveError::ParseErr(e) => write!(f, "{}", parse_message(e)),
+        }
+    }
+}
+
+impl<'a> ResolveError<'a> {
+    /// Part of the source code, where parsing failed.
+    /// Points into the source code handed to the parser,
+    /// so it can be used to find the line and column of the error.
+    pub fn span(&self) -> Option<&'a str> {
+        match self {
+            ResolveError::ParseErr(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                // the first error is the innermost one, i.e. where the parser actually gave up.
+                e.errors.first().map(|(input, _)| *input)
+            }
+            _ => None,
         }
     }
 }
 
+/// Describes a parse error, without dumping the remaining input of the parser.
+/// The outermost context (e.g. `function`) tells best what was being parsed.
+fn parse_message(e: &ast::NomErr) -> String {
+    let (nom::Err::Error(e) | nom::Err::Failure(e)) = e else {
+        return "unexpected end of file".to_string();
+    };
+
+    let context = e.errors.iter().rev().find_map(|(_, kind)| match kind {
+        VerboseErrorKind::Context(context) => Some(*context),
+        _ => None,
+    });
+
+    let expected = e.errors.first().map(|(_, kind)| match kind {
+        VerboseErrorKind::Char(c) => format!("expected '{c}'"),
+        VerboseErrorKind::Context(context) => format!("expected {context}"),
+        VerboseErrorKind::Nom(kind) => format!("expected {}", kind.description()),
+    });
+
+    match (context, expected) {
+        (Some(context), Some(expected)) => format!("failed to parse {context}: {expected}"),
+        (None, Some(expected)) => format!("failed to parse: {expected}"),
+        _ => "failed to parse".to_string(),
+    }
+}
+
 impl<'a> FileInfo<'a> {
     pub fn from_code(
+        file_id: FileId,
         filename: String,
         depmap: &HashMap<String, IdPath>,
         basepath: &IdPath,
         content: &'a str,
+        public_lines: &'a [usize],
     ) -> Result<Self, ResolveError<'a>> {
         // read in file and parse AST.
         let ast = Ast::from_source_code(content)?;
 
         // build up lookup table to resolve imported symbols.
-        let imports = resolve_imports(&ast, depmap, basepath)?;
+        let import_items = resolve_imports(&ast, depmap, basepath)?;
+        let (imports, glob_imports) = symbol_resolver(&import_items);
 
         Ok(FileInfo {
+            file_id,
             filename,
             imports,
+            glob_imports,
+            import_items,
+            source: content,
+            public_lines,
             ast,
         })
     }
+
+    /// Whether an item of the file is declared `pub`, e.g.
+    ///    pub fun length(s: String) -> Int
+    /// Buildin types are part of the language, and therefore always public.
+    /// `pub` is taken from the source code before parsing,
+    /// so the item is public, if its name is on a line declared `pub`.
+    pub fn is_public(&self, iditem: u16) -> bool {
+        let item = &self.ast.items[iditem as usize];
+        if let ast::body::BodyItem::BuildinTypeDecl(_) = item {
+            return true;
+        }
+
+        item_name(item)
+            .and_then(|name| offset_in(self.source, name))
+            .is_some_and(|offset| {
+                let (line, _) = line_column(self.source, offset);
+                self.public_lines.contains(&line)
+            })
+    }
+
+    /// Where an item of the file is declared, e.g. `std/io/console.sol:12:9`
+    pub fn declaration(&self, iditem: u16) -> String {
+        let item = &self.ast.items[iditem as usize];
+
+        match item_name(item).and_then(|name| offset_in(self.source, name)) {
+            Some(offset) => {
+                let (line, column) = line_column(self.source, offset);
+                format!("{}:{line}:{column}", self.filename)
+            }
+            None => self.filename.clone(),
+        }
+    }
+}
+
+/// Name of an item, as it appears in the source code.
+/// Tests don't have names.
+fn item_name<'a>(item: &ast::body::BodyItem<'a>) -> Option<&'a str> {
+    match item {
+        ast::body::BodyItem::Function(f) => Some(f.name.span),
+        ast::body::BodyItem::TypeDecl(t) => Some(t.name.span),
+        ast::body::BodyItem::BuildinTypeDecl(t) => Some(t.name.span),
+        ast::body::BodyItem::Let(l) => Some(l.identifier.span),
+        ast::body::BodyItem::Test(_) => None,
+    }
 }
 
 /// Resolve all imports from the ast to their global symbols for later lookup.
@@ -200,13 +401,13 @@ fn resolve_imports<'a>(
     ast: &Ast<'a>,
     depmap: &HashMap<String, IdPath>,
     basepath: &IdPath,
-) -> Result<SymbolResolver, ResolveError<'a>> {
-    let mut imports = HashMap::new();
+) -> Result<Vec<ImportItem<'a>>, ResolveError<'a>> {
+    let mut import_items = Vec::new();
 
     for import in ast.imports.iter() {
         // the ID path might be from a library, or from this project.
         // Here we switch based on that.
-        let mut path = if import.is_lib {
+        let path: IdPath = if import.is_lib {
             // now let's resolve this relative import (e.g. std.types.string) to an absolute path
             // that we can use as global identifier.
 
@@ -239,33 +440,121 @@ fn resolve_imports<'a>(
 
         match &import.items {
             Selection::All => {
-                unimplemented!("{}\n{}\n{}",
-                "found '..' selection.",
-                "Needs lookup for all symbols in a library.",
-                "Will need to happen eventually anyway, in order to check that every import is valid (and public)"
-            );
+                // e.g. use @std.string..
+                // the path is the module itself.
+                // Its symbols are looked up lazily, once all modules have been read in.
+                let last = import.path.last().expect("import path to not be empty");
+                import_items.push(ImportItem {
+                    symbol: None,
+                    module: path,
+                    span: last.span,
+                });
             }
             Selection::This => {
                 // the last symbol of the path was the concrete import item.
-                // just pop it of the path, and we're golden.
-                let symbol = path
-                    .pop()
+                // just split it off the path, and we're golden.
+                let (symbol, module) = path
+                    .split_last()
                     .expect("Concrete symbol to be at the end of import path");
-                imports.entry(symbol).or_insert_with(Vec::new).push(path);
+                let last = import.path.last().expect("import path to not be empty");
+                import_items.push(ImportItem {
+                    symbol: Some(symbol.clone()),
+                    module: module.to_vec(),
+                    span: last.span,
+                });
             }
             Selection::Items(s) => {
                 // Importing multiple symbols from this library.
                 // Add them all!
                 for symbol in s.iter() {
-                    let symbol = symbol.value.to_string();
-                    imports
-                        .entry(symbol)
-                        .or_insert_with(Vec::new)
-                        .push(path.clone());
+                    import_items.push(ImportItem {
+                        symbol: Some(symbol.value.to_string()),
+                        module: path.clone(),
+                        span: symbol.span,
+                    });
                 }
             }
         }
     }
 
-    Ok(imports)
+    Ok(import_items)
+}
+
+/// Lookup table of explicitly imported symbols,
+/// along with the modules imported with a wildcard.
+fn symbol_resolver(import_items: &[ImportItem]) -> (SymbolResolver, Vec<IdPath>) {
+    let mut imports = HashMap::new();
+    let mut glob_imports = Vec::new();
+
+    for item in import_items {
+        match &item.symbol {
+            Some(symbol) => imports
+                .entry(symbol.clone())
+                .or_insert_with(Vec::new)
+                .push(item.module.clone()),
+            None if !glob_imports.contains(&item.module) => {
+                glob_imports.push(item.module.clone());
+            }
+            None => {}
+        }
+    }
+
+    (imports, glob_imports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(sources: &[&'static str]) -> Module<'static> {
+        let mut module = Module::new(0, vec!["app".to_string()]);
+        for (i, source) in sources.iter().enumerate() {
+            let file = FileInfo::from_code(
+                FileId(i as u32),
+                format!("file{i}.sol"),
+                &HashMap::new(),
+                &module.idmodule,
+                source,
+                &[],
+            )
+            .unwrap();
+            module.add_file(file);
+        }
+
+        module
+    }
+
+    #[test]
+    fn symbols_are_indexed_across_files() {
+        let module = module(&["fun f(x: Int) = x\nlet v = 1\n", "fun f(x: String) = x\n"]);
+
+        assert_eq!(module.find("f").unwrap().len(), 2);
+        assert_eq!(module.find("v").unwrap().len(), 1);
+        assert!(matches!(module.find("g"), Err(FindError::NotFound(_))));
+    }
+
+    #[test]
+    fn duplicates_across_files() {
+        let module = module(&[
+            "fun f(x: Int) = x\nfun f(x: String) = x\nlet v = 1\n",
+            "fun f(x: Int) = x\nfun g() = 1\nlet v = 2\n",
+        ]);
+
+        let mut found = module
+            .duplicates()
+            .into_iter()
+            .map(|(name, first, duplicate)| (name, first.1, duplicate.1))
+            .collect::<Vec<_>>();
+        found.sort();
+
+        // overloads with other argument types are no duplicates.
+        assert_eq!(found, vec![("f", 0, 1), ("v", 0, 1)]);
+    }
+
+    #[test]
+    fn overloads_are_no_duplicates() {
+        let module = module(&["fun f() = 1\nfun f(x: Int) = x\nfun f(x: Int, y: Int) = x\n"]);
+
+        assert!(module.duplicates().is_empty());
+    }
 }
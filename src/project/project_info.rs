@@ -1,68 +1,484 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use hotel::HotelMap;
+use rayon::prelude::*;
+use semver::VersionReq;
+use thiserror::Error;
 
 use crate::{
     util::{self, IdPath},
     Config,
 };
 
-use super::{Module, Project};
+use super::{
+    installed_versions, LockedLibrary, Lockfile, Module, Project, Source, SourceDb, SourceFile,
+    Workspace, LOCKFILE,
+};
 
 pub type ProjectInfo = HotelMap<IdPath, Project>;
 
+/// Reads the target project at the project root, along with all its dependencies.
+/// Members of a workspace share the solar.lock of the workspace,
+/// which is kept up to date with the dependencies of all members.
 pub fn read_all_projects(config: &Config) -> anyhow::Result<ProjectInfo> {
-    let mut projects = HotelMap::new();
-    let p = Project::open(&config.project_root, util::target_id())
-        .with_context(|| format!("opening project at {}", config.project_root))?;
-
-    fn insert_all(
-        p: Project,
-        projects: &mut HotelMap<IdPath, Project>,
-        config: &Config,
-    ) -> anyhow::Result<()> {
+    let root = &config.project_root;
+
+    // script mode, no solar.yaml (and no solar.lock) needed
+    if Path::new(root).is_file() {
+        let p = Project::script(root, util::target_id(), config)
+            .with_context(|| format!("opening project at {root}"))?;
+
+        let projects = read_target(p, config, None)?;
+        report_roots(std::slice::from_ref(&projects));
+
+        return Ok(projects);
+    }
+
+    if let Some(workspace) = Workspace::read(root)? {
+        anyhow::bail!(
+            "{root} is a workspace, select one of its members: {}",
+            workspace.members.join(", ")
+        );
+    }
+
+    if let Some(workspace) = Workspace::containing(root)? {
+        let index = workspace
+            .member_index(root)?
+            .expect("workspace to contain the project");
+        let mut members = read_workspace(config, &workspace)?;
+
+        return Ok(members.swap_remove(index));
+    }
+
+    let lock = Lockfile::read(root)?;
+    let p = Project::open(root, util::target_id(), config, lock.as_ref())
+        .with_context(|| format!("opening project at {root}"))?;
+    let projects = read_target(p, config, lock.as_ref())?;
+
+    report_roots(std::slice::from_ref(&projects));
+    update_lockfile(root, config, lock, std::slice::from_ref(&projects))?;
+
+    Ok(projects)
+}
+
+/// Reads every member of a workspace as target project, along with its dependencies.
+/// Dependencies are resolved using the solar.lock of the workspace,
+/// which is updated afterwards with the libraries of all members.
+pub fn read_workspace(config: &Config, workspace: &Workspace) -> anyhow::Result<Vec<ProjectInfo>> {
+    let lock = Lockfile::read(&workspace.root)?;
+
+    let read_members = |lock: Option<&Lockfile>| {
+        workspace
+            .members
+            .iter()
+            .map(|dir| {
+                let p = Project::open(dir, util::target_id(), config, lock)
+                    .with_context(|| format!("opening workspace member at {dir}"))?;
+                read_target(p, config, lock)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    };
+
+    let mut members = read_members(lock.as_ref())?;
+
+    // members resolve their dependencies independently at first,
+    // read them again if they can agree on common versions.
+    if let Some(shared) = shared_versions(config, &members) {
+        members = read_members(Some(&shared))?;
+    }
+
+    report_roots(&members);
+    update_lockfile(&workspace.root, config, lock, &members)?;
+
+    Ok(members)
+}
+
+/// Installed libraries, that have been resolved to several versions across the members of a workspace,
+/// are unified to the highest version matching all requirements, if there is one.
+/// Returns the versions to prefer when resolving again, or None if nothing can be unified.
+fn shared_versions(config: &Config, members: &[ProjectInfo]) -> Option<Lockfile> {
+    let mut requirements: BTreeMap<(String, String), Vec<VersionReq>> = BTreeMap::new();
+    let mut resolved: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+
+    for (_, p) in members.iter().flat_map(|projects| projects.iter_values()) {
         for dep in p.config.deps() {
-            let path = dep.basepath();
-            // skip project, if we have already read it.
-            if projects.contains(&path) {
+            if dep.path.is_some() || dep.repo.is_some() {
                 continue;
             }
 
-            let dir = dep.dir(&config);
-            let p =
-                Project::open(&dir, path).with_context(|| format!("opening project at {}", dir))?;
-            insert_all(p, projects, config)?;
+            if let Ok(req) = VersionReq::parse(&dep.requirement) {
+                let key = (dep.name, dep.publisher);
+                requirements.entry(key).or_default().push(req);
+            }
         }
 
-        projects.insert(p.basepath.clone(), p);
+        for dep in p.deps.iter().filter(|d| d.source == Source::SolarPath) {
+            let key = (dep.name.clone(), dep.publisher.clone());
+            resolved.entry(key).or_default().insert(dep.version.clone());
+        }
+    }
+
+    let mut unified = false;
+    let mut libraries = Vec::new();
+
+    for ((name, publisher), versions) in resolved {
+        let reqs = &requirements[&(name.clone(), publisher.clone())];
+        let shared = (versions.len() > 1)
+            .then(|| {
+                installed_versions(&config.solarpath, &name, &publisher)
+                    .into_iter()
+                    .rev()
+                    .find(|v| reqs.iter().all(|req| req.matches(v)))
+            })
+            .flatten();
 
-        Ok(())
+        let versions = match shared {
+            Some(version) => {
+                unified = true;
+                vec![version.to_string()]
+            }
+            None => versions.into_iter().collect(),
+        };
+
+        for version in versions {
+            libraries.push(LockedLibrary {
+                name: name.clone(),
+                publisher: publisher.clone(),
+                version,
+                source: Source::SolarPath.to_string(),
+                // only used for resolution, never written.
+                hash: None,
+            });
+        }
     }
 
-    insert_all(p, &mut projects, config)?;
+    unified.then_some(Lockfile { libraries })
+}
+
+/// Reads all dependencies of the target project `p`.
+fn read_target(
+    p: Project,
+    config: &Config,
+    lock: Option<&Lockfile>,
+) -> anyhow::Result<ProjectInfo> {
+    let mut projects = HotelMap::new();
+
+    let name = p.config.name.clone();
+    let mut chain = vec![(canonical_dir(&p.fsroot)?, name)];
+    insert_all(p, &mut projects, &mut chain, config, lock)?;
 
     Ok(projects)
 }
 
+/// Reads all dependencies of `p` depth first, inserting every project after its dependencies.
+/// Projects are identified by their basepath,
+/// so libraries reached via several paths (diamonds) are read only once,
+/// while different versions of the same library are read side by side.
+/// `chain` contains the directory and name of every project currently being read,
+/// starting at the target project, and is used to detect dependency cycles.
+fn insert_all(
+    p: Project,
+    projects: &mut ProjectInfo,
+    chain: &mut Vec<(PathBuf, String)>,
+    config: &Config,
+    lock: Option<&Lockfile>,
+) -> anyhow::Result<()> {
+    // dependencies are sorted by basepath, so the walk is deterministic.
+    for dep in &p.deps {
+        let dir = &dep.dir;
+        let canonical = canonical_dir(dir)?;
+
+        if let Some(start) = chain.iter().position(|(d, _)| d == &canonical) {
+            let cycle = chain[start..]
+                .iter()
+                .chain(std::iter::once(&chain[start]))
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            anyhow::bail!("dependency cycle: {cycle}");
+        }
+
+        let path = dep.basepath();
+        // skip project, if we have already read it.
+        if projects.contains(&path) {
+            continue;
+        }
+
+        let p = Project::open(dir, path, config, lock)
+            .with_context(|| format!("opening project at {dir}"))?;
+
+        let name = format!("{}({}) {}", dep.name, dep.publisher, dep.version);
+        chain.push((canonical, name));
+        insert_all(p, projects, chain, config, lock)?;
+        chain.pop();
+    }
+
+    projects.insert(p.basepath.clone(), p);
+
+    Ok(())
+}
+
+/// Directory of a project, used to recognize it regardless of how it is referred to.
+fn canonical_dir(dir: &str) -> anyhow::Result<PathBuf> {
+    Path::new(dir)
+        .canonicalize()
+        .with_context(|| format!("finding project directory {dir}"))
+}
+
+/// Reports libraries, that are installed in several roots of the solarpath,
+/// along with the root they have been taken from.
+fn report_roots(targets: &[ProjectInfo]) {
+    let mut reported = BTreeMap::new();
+    for (_, p) in targets.iter().flat_map(|projects| projects.iter_values()) {
+        for dep in p.deps.iter().filter(|d| !d.also_found_in.is_empty()) {
+            reported.insert(dep.basepath(), dep);
+        }
+    }
+
+    for dep in reported.values() {
+        eprintln!(
+            "note: using {}({}) {} from {}, also found in {}",
+            dep.name,
+            dep.publisher,
+            dep.version,
+            dep.dir,
+            dep.also_found_in.join(", ")
+        );
+    }
+}
+
+/// Records the resolved libraries in the solar.lock inside `dir`.
+/// In locked mode, the resolution must match the existing lockfile instead.
+fn update_lockfile(
+    dir: &str,
+    config: &Config,
+    lock: Option<Lockfile>,
+    targets: &[ProjectInfo],
+) -> anyhow::Result<()> {
+    let resolved = Lockfile::from_projects(dir, targets)?;
+
+    if config.locked {
+        let Some(lock) = lock else {
+            anyhow::bail!("running in locked mode, but there is no {LOCKFILE}");
+        };
+
+        return lock.verify(&resolved);
+    }
+
+    if lock.as_ref() != Some(&resolved) {
+        resolved.write(dir)?;
+    }
+
+    Ok(())
+}
+
 /// Mapping from IdPaths/ModulePaths (use @std.0.1.0.types.string.String) to all modules.
 /// ASTs can be found inside the modules.
-pub type GlobalModules<'a> = HashMap<IdPath, Module<'a>>;
+/// Sorted by path, so iterating over the modules doesn't depend on the filesystem or hashing.
+pub type GlobalModules<'a> = BTreeMap<IdPath, Module<'a>>;
+
+/// Every error that occurred while reading and parsing source files.
+/// Loading continues past broken files,
+/// so that all of them can be reported in a single run.
+#[derive(Debug, Default, Error)]
+pub struct LoadErrors(pub Vec<anyhow::Error>);
+
+impl LoadErrors {
+    pub fn push(&mut self, error: anyhow::Error) {
+        self.0.push(error);
+    }
+
+    /// Fails, if any error has been collected.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        Err(self)
+    }
+}
+
+impl std::fmt::Display for LoadErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for error in &self.0 {
+            writeln!(f, "{error:#}\n")?;
+        }
+
+        match self.0.len() {
+            1 => write!(f, "could not load solar files due to previous error"),
+            n => write!(f, "could not load solar files due to {n} previous errors"),
+        }
+    }
+}
+
+/// Reads the source code of all projects into memory.
+/// Files are read in parallel, but added to the database in a fixed order,
+/// so that every file receives the same FileId on every run.
+/// Files that can't be read are skipped and reported in `errors`.
+pub fn read_sources(projects: &ProjectInfo, errors: &mut LoadErrors) -> SourceDb {
+    let mut paths = Vec::new();
+    for (project_id, project) in projects.iter_values() {
+        let files = match project.source_modules() {
+            Ok(files) => files,
+            Err(e) => {
+                errors.push(e.context(format!("reading project {}", project.fsroot)));
+                continue;
+            }
+        };
+
+        for file in files {
+            match file {
+                Ok((path, module)) => paths.push((project_id, path, module)),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    let files = paths
+        .into_par_iter()
+        .map(|(project_id, path, module)| {
+            SourceFile::read(path.clone(), project_id, module)
+                .with_context(|| format!("reading file {path}"))
+        })
+        .collect::<Vec<_>>();
+
+    let mut sources = SourceDb::new();
+    for file in files {
+        match file {
+            Ok(file) => {
+                sources.add(file);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    sources
+}
 
 /// create global mapping of ModulePaths to Modules
 /// i.e. across all dependencies and sub-dependencies
-pub fn read_modules(projects: &ProjectInfo) -> anyhow::Result<GlobalModules<'_>> {
-    let mut modules = HashMap::new();
+/// The ASTs of all modules borrow from the source database.
+/// Files are parsed in parallel, and added to their modules in order of their FileId.
+/// Files that can't be parsed are left out and reported in `errors`,
+/// just like symbols declared more than once inside a module.
+pub fn read_modules<'a>(
+    projects: &ProjectInfo,
+    sources: &'a SourceDb,
+    errors: &mut LoadErrors,
+) -> GlobalModules<'a> {
+    let files = sources.iter().collect::<Vec<_>>();
 
-    for (project_id, project) in projects.iter_values() {
-        let symbol_table = project
-            .read_all(project_id)
-            .context(format!("reading project {}", project.fsroot))?;
+    let parsed = files
+        .par_iter()
+        .map(|&(file_id, file)| {
+            let project = projects
+                .get_by_index(file.project_id)
+                .expect("project of source file to be read in");
+
+            project.parse(file_id, file)
+        })
+        .collect::<Vec<_>>();
+
+    let mut modules = BTreeMap::new();
+    for ((_, file), fileinfo) in files.into_iter().zip(parsed) {
+        let fileinfo = match fileinfo {
+            Ok(fileinfo) => fileinfo,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        modules
+            .entry(file.module.clone())
+            .or_insert_with(|| Module::new(file.project_id, file.module.clone()))
+            .add_file(fileinfo);
+    }
+
+    for module in modules.values() {
+        for (name, first, duplicate) in module.duplicates() {
+            errors.push(anyhow::anyhow!(
+                "{}: {name} is already declared at {}",
+                module.declaration(duplicate),
+                module.declaration(first)
+            ));
+        }
+    }
+
+    modules
+}
 
-        for (sym, path) in symbol_table.into_iter() {
-            modules.insert(sym, path);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SOLAR_PATH;
+    use std::collections::HashMap;
+
+    /// Creates a project inside a new temporary directory,
+    /// depending on the projects in the sibling directories `deps`.
+    fn project(test: &str, name: &str, deps: &[&str]) -> String {
+        let dir = std::env::temp_dir()
+            .join(format!("solar-projects-{}", std::process::id()))
+            .join(test)
+            .join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut yaml = format!("name: {name}\npublisher: acme\nversion: 0.1.0\n");
+        if !deps.is_empty() {
+            yaml.push_str("dependencies:\n");
+            for dep in deps {
+                yaml.push_str(&format!("  {dep}(acme): {{ path: ../{dep} }}\n"));
+            }
         }
+        std::fs::write(dir.join("solar.yaml"), yaml).unwrap();
+
+        dir.display().to_string()
     }
 
-    Ok(modules)
+    fn read(root: &str) -> anyhow::Result<ProjectInfo> {
+        let solarpath = std::env::temp_dir().display().to_string();
+        let overrides = HashMap::from([(SOLAR_PATH.to_string(), solarpath)]);
+        let config = Config::load(root.to_string(), overrides).unwrap();
+
+        let p = Project::open(root, util::target_id(), &config, None)?;
+        read_target(p, &config, None)
+    }
+
+    #[test]
+    fn diamonds_are_read_once() {
+        let root = project("diamond", "app", &["left", "right"]);
+        project("diamond", "left", &["base"]);
+        project("diamond", "right", &["base"]);
+        project("diamond", "base", &[]);
+
+        let projects = read(&root).unwrap();
+        assert_eq!(projects.iter_values().count(), 4);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let root = project("cycle", "app", &["lib"]);
+        project("cycle", "lib", &["util"]);
+        project("cycle", "util", &["lib"]);
+
+        let e = read(&root).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "dependency cycle: lib(acme) 0.1.0 -> util(acme) 0.1.0 -> lib(acme) 0.1.0"
+        );
+    }
+
+    #[test]
+    fn cycles_through_the_target_are_reported() {
+        let root = project("self", "app", &["lib"]);
+        project("self", "lib", &["app"]);
+
+        let e = read(&root).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "dependency cycle: app -> lib(acme) 0.1.0 -> app"
+        );
+    }
 }
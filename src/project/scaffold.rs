@@ -0,0 +1,127 @@
+/// This file contains code
+/// for creating new solar projects.
+use super::{latest_installed, DependencySpec, SolarConfig, STD_NAME, STD_PUBLISHER};
+use anyhow::{bail, Context};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Entry point of applications, if the installed std library can be used.
+const MAIN_WITH_STD: &str = r#"use @std.io.println
+
+fun main() =
+    println "Hello, World!"
+"#;
+
+/// Entry point of applications, if there is no std library installed.
+const MAIN_WITHOUT_STD: &str = r#"
+fun main() =
+    buildin_print "Hello, World!\n"
+"#;
+
+/// Source file of libraries
+const LIB: &str = r#"
+# Returns a friendly greeting
+pub fun greeting() -> String = "Hello, World!"
+"#;
+
+/// Options for creating a new project
+pub struct Scaffold<'a> {
+    /// Name of the project. Defaults to the name of the directory.
+    pub name: Option<&'a str>,
+    pub publisher: Option<&'a str>,
+    /// Create a library instead of an application
+    pub lib: bool,
+}
+
+impl Scaffold<'_> {
+    /// Creates a solar.yaml and an initial source file inside `dir`.
+    /// The directory is created, if it doesn't exist yet.
+    /// Returns the generated config.
+    pub fn create(&self, dir: &Path, solarpath: &[String]) -> anyhow::Result<SolarConfig> {
+        let solarfile = dir.join("solar.yaml");
+        if solarfile.exists() {
+            bail!("{} already exists", solarfile.display());
+        }
+
+        let name = match self.name {
+            Some(name) => name.to_string(),
+            None => {
+                dir_name(dir).context("derive project name from directory, please supply one")?
+            }
+        };
+        validate_name(&name)?;
+        if let Some(publisher) = self.publisher {
+            validate_name(publisher)?;
+        }
+
+        let std_version = latest_installed(solarpath, STD_NAME, STD_PUBLISHER);
+        if std_version.is_none() {
+            eprintln!(
+                "warning: no std library found in {}, creating project without dependencies",
+                solarpath.join(":")
+            );
+        }
+
+        let config = SolarConfig {
+            name,
+            publisher: self.publisher.map(String::from),
+            version: "0.1.0".to_string(),
+            description: None,
+            author: None,
+            authors: None,
+            dependencies: std_version.as_ref().map(|version| {
+                let key = format!("{STD_NAME}({STD_PUBLISHER})");
+                HashMap::from([(key, DependencySpec::Version(version.clone()))])
+            }),
+            include: None,
+            exclude: None,
+        };
+
+        let (filename, source) = match (self.lib, &std_version) {
+            (true, _) => ("lib.sol", LIB),
+            (false, Some(_)) => ("main.sol", MAIN_WITH_STD),
+            (false, None) => ("main.sol", MAIN_WITHOUT_STD),
+        };
+
+        let sourcefile = dir.join(filename);
+        if sourcefile.exists() {
+            bail!("{} already exists", sourcefile.display());
+        }
+
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating directory {}", dir.display()))?;
+
+        let yaml = serde_yaml::to_string(&config).context("serialize solar.yaml")?;
+        std::fs::write(&solarfile, yaml)
+            .with_context(|| format!("writing {}", solarfile.display()))?;
+        std::fs::write(&sourcefile, source)
+            .with_context(|| format!("writing {}", sourcefile.display()))?;
+
+        Ok(config)
+    }
+}
+
+/// Name of the directory, also if it is given as e.g. `.`
+fn dir_name(dir: &Path) -> Option<String> {
+    let name = match dir.file_name() {
+        Some(name) => name.to_owned(),
+        None => dir.canonicalize().ok()?.file_name()?.to_owned(),
+    };
+
+    name.into_string().ok()
+}
+
+/// Names and publishers end up in dependency keys like `name(publisher)`
+/// and in directory names, so only a restricted set of characters is allowed.
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        bail!("invalid name '{name}', only letters, digits, '-' and '_' are allowed");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,364 @@
+/// This file contains code
+/// for keeping the source code of all solar files in memory.
+/// ASTs borrow from the database, so files can only be reloaded or freed
+/// once the ASTs referring to them have been dropped.
+use crate::util::IdPath;
+use std::fmt;
+
+/// Index of a file inside the [SourceDb].
+/// Ids are never reused, also not after a file has been freed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(pub u32);
+
+/// Source code of a single solar file
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: String,
+    pub text: String,
+    /// Project the file belongs to, i.e. its index in the ProjectInfo
+    pub project_id: usize,
+    /// Module the file belongs to
+    pub module: IdPath,
+    /// Lines (starting at 1) of items, that have been declared `pub`
+    pub public_lines: Vec<usize>,
+}
+
+impl SourceFile {
+    /// Reads the file at `path`.
+    pub fn read(path: String, project_id: usize, module: IdPath) -> std::io::Result<Self> {
+        let mut text = read_source(&path)?;
+        let public_lines = strip_visibility(&mut text);
+
+        Ok(SourceFile {
+            path,
+            text,
+            project_id,
+            module,
+            public_lines,
+        })
+    }
+
+    /// Position of a span inside this file.
+    /// Returns None, if the span doesn't point into this file.
+    pub fn locate(&self, id: FileId, span: &str) -> Option<Location> {
+        let offset = offset_in(&self.text, span)?;
+        let (line, column) = line_column(&self.text, offset);
+
+        Some(Location {
+            file: id,
+            path: self.path.clone(),
+            line,
+            column,
+        })
+    }
+
+    /// Renders a message about a span of this file,
+    /// along with the line the span starts in.
+    pub fn diagnostic(&self, id: FileId, span: &str, message: impl fmt::Display) -> String {
+        match self.locate(id, span) {
+            Some(location) => format!("{location}: {message}\n{}", self.excerpt(&location)),
+            None => format!("{}: {message}", self.path),
+        }
+    }
+
+    /// Renders the line of `location`, with a marker below the column, e.g.
+    ///
+    ///       |
+    ///     3 | let x = 1 +
+    ///       |            ^
+    fn excerpt(&self, location: &Location) -> String {
+        let line = self.text.lines().nth(location.line - 1).unwrap_or_default();
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let marker = " ".repeat(location.column - 1);
+
+        format!("{gutter} |\n{number} | {line}\n{gutter} | {marker}^")
+    }
+}
+
+/// Position of a span inside a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: FileId,
+    pub path: String,
+    /// starting at 1
+    pub line: usize,
+    /// starting at 1, counted in characters
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
+}
+
+/// Owns the source code of all files that have been read in.
+#[derive(Debug, Default)]
+pub struct SourceDb {
+    files: Vec<Option<SourceFile>>,
+}
+
+impl SourceDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, e.g. one that has been read in on another thread.
+    pub fn add(&mut self, file: SourceFile) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(Some(file));
+        id
+    }
+
+    pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.0 as usize)?.as_ref()
+    }
+
+    /// All files that haven't been freed, in the order they have been added.
+    pub fn iter(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files
+            .iter()
+            .enumerate()
+            .filter_map(|(id, file)| Some((FileId(id as u32), file.as_ref()?)))
+    }
+
+    /// Finds the file and position a span (e.g. of an AST node) points into.
+    /// Returns None, if the span doesn't point into any file of this database.
+    pub fn locate(&self, span: &str) -> Option<Location> {
+        self.iter().find_map(|(id, file)| file.locate(id, span))
+    }
+
+    /// Renders a message about a span (e.g. of an AST node),
+    /// along with the line the span starts in.
+    pub fn diagnostic(&self, span: &str, message: impl fmt::Display) -> String {
+        let Some(location) = self.locate(span) else {
+            return message.to_string();
+        };
+
+        let file = self.get(location.file).expect("located file to be loaded");
+        format!("{location}: {message}\n{}", file.excerpt(&location))
+    }
+}
+
+/// Managing files, once the ASTs referring to them have been dropped.
+/// The command line reads every file just once, so it doesn't need these (yet),
+/// they are meant for long running tools, e.g. a language server.
+#[allow(dead_code)]
+impl SourceDb {
+    /// Reads the file at `path` and adds it to the database.
+    pub fn load(
+        &mut self,
+        path: String,
+        project_id: usize,
+        module: IdPath,
+    ) -> std::io::Result<FileId> {
+        let file = SourceFile::read(path, project_id, module)?;
+        Ok(self.add(file))
+    }
+
+    /// Reads a file in again, e.g. after it has been changed on disk.
+    /// Does nothing, if the file has been freed.
+    pub fn reload(&mut self, id: FileId) -> std::io::Result<()> {
+        let Some(Some(file)) = self.files.get_mut(id.0 as usize) else {
+            return Ok(());
+        };
+
+        *file = SourceFile::read(file.path.clone(), file.project_id, file.module.clone())?;
+        Ok(())
+    }
+
+    /// Releases the source code of a file.
+    pub fn free(&mut self, id: FileId) {
+        if let Some(file) = self.files.get_mut(id.0 as usize) {
+            *file = None;
+        }
+    }
+
+    /// Finds the file, that has been read from `path`.
+    pub fn find(&self, path: &str) -> Option<FileId> {
+        self.iter()
+            .find(|(_, file)| file.path == path)
+            .map(|(id, _)| id)
+    }
+}
+
+/// Byte offset of `span` inside `text`.
+/// Returns None, if the span doesn't point into `text`.
+pub fn offset_in(text: &str, span: &str) -> Option<usize> {
+    let start = span.as_ptr() as usize;
+    let begin = text.as_ptr() as usize;
+    if start < begin || start + span.len() > begin + text.len() {
+        return None;
+    }
+
+    Some(start - begin)
+}
+
+/// Line and column of a byte offset into `text`, both starting at 1.
+/// Columns are counted in characters.
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
+}
+
+/// Reads the source code of a file.
+fn read_source(path: &str) -> std::io::Result<String> {
+    let mut text = std::fs::read_to_string(path)?;
+    strip_shebang(&mut text);
+    Ok(text)
+}
+
+/// Blanks out a leading `#!` line,
+/// so solar files can be made executable.
+/// The line break is kept, so that line numbers stay intact.
+fn strip_shebang(source_code: &mut String) {
+    if !source_code.starts_with("#!") {
+        return;
+    }
+
+    let end = source_code.find('\n').unwrap_or(source_code.len());
+    source_code.replace_range(..end, "");
+}
+
+/// Keywords, that items may be declared with
+const ITEM_KEYWORDS: &[&str] = &["fun", "type", "let"];
+
+/// Blanks out the `pub` in front of items, e.g.
+///    pub fun length(s: String) -> Int
+/// and returns the lines of all items declared this way.
+/// The parser doesn't know about visibility,
+/// so it is taken from the source code before parsing,
+/// just like the shebang line.
+/// Offsets stay intact, as `pub` is replaced by spaces.
+fn strip_visibility(source_code: &mut String) -> Vec<usize> {
+    let mut public = Vec::new();
+    let mut offset = 0;
+
+    for (index, line) in source_code.split_inclusive('\n').enumerate() {
+        let indent = line.len() - line.trim_start().len();
+        let declaration = line[indent..]
+            .strip_prefix("pub")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .and_then(|rest| rest.split_whitespace().next());
+
+        if declaration.is_some_and(|keyword| ITEM_KEYWORDS.contains(&keyword)) {
+            public.push((index + 1, offset + indent));
+        }
+
+        offset += line.len();
+    }
+
+    for &(_, start) in &public {
+        source_code.replace_range(start..start + "pub".len(), "   ");
+    }
+
+    public.into_iter().map(|(line, _)| line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `text` into a new file inside the temporary directory.
+    fn temp_file(name: &str, text: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("solar-source-db-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn load_and_find() {
+        let a = temp_file("find_a.sol", "fun a() = 1\n");
+        let b = temp_file("find_b.sol", "fun b() = 2\n");
+
+        let mut db = SourceDb::new();
+        let id_a = db.load(a.clone(), 0, vec!["self".to_string()]).unwrap();
+        let id_b = db.load(b.clone(), 0, vec!["self".to_string()]).unwrap();
+
+        assert_ne!(id_a, id_b);
+        assert_eq!(db.find(&a), Some(id_a));
+        assert_eq!(db.find(&b), Some(id_b));
+        assert_eq!(db.find("missing.sol"), None);
+        assert_eq!(db.get(id_b).unwrap().text, "fun b() = 2\n");
+    }
+
+    #[test]
+    fn reload_reads_changed_file() {
+        let path = temp_file("reload.sol", "fun a() = 1\n");
+
+        let mut db = SourceDb::new();
+        let id = db.load(path.clone(), 0, Vec::new()).unwrap();
+
+        std::fs::write(&path, "pub fun a() = 2\n").unwrap();
+        db.reload(id).unwrap();
+
+        let file = db.get(id).unwrap();
+        assert_eq!(file.text, "    fun a() = 2\n");
+        assert_eq!(file.public_lines, vec![1]);
+        assert_eq!(db.find(&path), Some(id));
+    }
+
+    #[test]
+    fn free_keeps_ids_of_other_files() {
+        let a = temp_file("free_a.sol", "fun a() = 1\n");
+        let b = temp_file("free_b.sol", "fun b() = 2\n");
+
+        let mut db = SourceDb::new();
+        let id_a = db.load(a.clone(), 0, Vec::new()).unwrap();
+        let id_b = db.load(b, 0, Vec::new()).unwrap();
+
+        db.free(id_a);
+        assert!(db.get(id_a).is_none());
+        assert_eq!(db.find(&a), None);
+        assert_eq!(db.iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![id_b]);
+
+        // freed files are not read in again.
+        db.reload(id_a).unwrap();
+        assert!(db.get(id_a).is_none());
+
+        // ids are not reused.
+        let c = temp_file("free_c.sol", "fun c() = 3\n");
+        let id_c = db.load(c, 0, Vec::new()).unwrap();
+        assert_ne!(id_c, id_a);
+    }
+
+    #[test]
+    fn strip_shebang_keeps_line_numbers() {
+        let mut text = "#!/usr/bin/env solar\nfun main() = 1\n".to_string();
+        strip_shebang(&mut text);
+        assert_eq!(text, "\nfun main() = 1\n");
+
+        let mut text = "fun main() = 1 #!\n".to_string();
+        strip_shebang(&mut text);
+        assert_eq!(text, "fun main() = 1 #!\n");
+
+        let mut text = "#!/usr/bin/env solar".to_string();
+        strip_shebang(&mut text);
+        assert_eq!(text, "");
+    }
+
+    #[test]
+    fn positions_of_spans() {
+        let text = "fun a() = 1\nfun bäm() = 2\n";
+
+        let span = &text[17..20];
+        assert_eq!(span, "äm");
+        let offset = offset_in(text, span).unwrap();
+        assert_eq!(offset, 17);
+        // columns are counted in characters, not bytes.
+        assert_eq!(line_column(text, offset), (2, 6));
+        assert_eq!(line_column(text, 0), (1, 1));
+        assert_eq!(line_column(text, text.len()), (3, 1));
+
+        let other = String::from("fun a() = 1\n");
+        assert_eq!(offset_in(text, &other), None);
+    }
+}
@@ -0,0 +1,141 @@
+/// This file contains code
+/// for deciding which files in the directory of a project are solar source files.
+use super::SolarConfig;
+use anyhow::Context;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// File inside the project root, listing additional exclude patterns, one per line.
+pub const SOLARIGNORE: &str = ".solarignore";
+
+/// Files considered to be source files, if the solar.yaml doesn't declare `include` patterns.
+const DEFAULT_INCLUDE: &[&str] = &["*.sol"];
+
+/// Include and exclude patterns of a project.
+///
+/// Patterns are globs relative to the project root, e.g. `src/**/*.sol`.
+/// Like in a .gitignore, patterns without `/` match at any depth,
+/// while a leading `/` anchors a pattern at the project root.
+/// Directories matching an exclude pattern are skipped entirely.
+pub struct SourceFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl SourceFilter {
+    /// Collects the patterns of the solar.yaml and the .solarignore inside `fsroot`.
+    pub fn new(fsroot: &str, config: &SolarConfig) -> anyhow::Result<Self> {
+        let include = match &config.include {
+            Some(patterns) => glob_set(patterns)?,
+            None => glob_set(DEFAULT_INCLUDE)?,
+        };
+
+        let mut exclude = config.exclude.clone().unwrap_or_default();
+
+        let ignorefile = Path::new(fsroot).join(SOLARIGNORE);
+        if ignorefile.is_file() {
+            let content = std::fs::read_to_string(&ignorefile)
+                .with_context(|| format!("reading {}", ignorefile.display()))?;
+            exclude.extend(parse_ignore_file(&content));
+        }
+
+        let exclude = glob_set(&exclude).with_context(|| format!("reading {SOLARIGNORE}"))?;
+
+        Ok(SourceFilter { include, exclude })
+    }
+
+    /// Whether the walk should descend into the directory at `relative` (relative to the project root).
+    pub fn visit_dir(&self, relative: &Path) -> bool {
+        !self.exclude.is_match(relative)
+    }
+
+    /// Whether the file at `relative` (relative to the project root) is a source file.
+    pub fn is_source(&self, relative: &Path) -> bool {
+        let is_solar = relative.extension().is_some_and(|ext| ext == "sol");
+
+        is_solar && self.include.is_match(relative) && !self.exclude.is_match(relative)
+    }
+}
+
+/// Patterns of a .solarignore file.
+/// Empty lines and lines starting with `#` are ignored.
+fn parse_ignore_file(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Compiles a list of patterns into a single matcher.
+pub fn glob_set(patterns: &[impl AsRef<str>]) -> anyhow::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        let glob = GlobBuilder::new(&normalize(pattern))
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("invalid pattern '{pattern}'"))?;
+
+        builder.add(glob);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Turns gitignore style patterns into globs matching paths relative to the project root.
+fn normalize(pattern: &str) -> String {
+    let pattern = pattern.trim_end_matches('/');
+
+    if let Some(anchored) = pattern.strip_prefix('/') {
+        return anchored.to_string();
+    }
+
+    if pattern.contains('/') {
+        pattern.to_string()
+    } else {
+        format!("**/{pattern}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_like_gitignore() {
+        assert_eq!(normalize("*.sol"), "**/*.sol");
+        assert_eq!(normalize("target/"), "**/target");
+        assert_eq!(normalize("/build"), "build");
+        assert_eq!(normalize("src/**/*.sol"), "src/**/*.sol");
+    }
+
+    #[test]
+    fn glob_set_matches_relative_paths() {
+        let set = glob_set(&["*.sol", "/build", "src/*.sol"]).unwrap();
+
+        assert!(set.is_match("main.sol"));
+        assert!(set.is_match("deep/nested/main.sol"));
+        assert!(set.is_match("build"));
+        assert!(!set.is_match("nested/build"));
+
+        // `*` doesn't cross directories
+        let set = glob_set(&["src/*.sol"]).unwrap();
+        assert!(set.is_match("src/main.sol"));
+        assert!(!set.is_match("src/nested/main.sol"));
+    }
+
+    #[test]
+    fn glob_set_reports_invalid_pattern() {
+        let e = glob_set(&["[a"]).unwrap_err();
+        assert!(format!("{e:#}").starts_with("invalid pattern '[a'"));
+    }
+
+    #[test]
+    fn ignore_file_skips_comments() {
+        let patterns = parse_ignore_file("# generated\n\ntarget/\n  /build  \n");
+        assert_eq!(patterns, vec!["target/", "/build"]);
+    }
+}
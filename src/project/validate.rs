@@ -0,0 +1,263 @@
+/// This file contains code
+/// for validating solar.yaml files,
+/// reporting problems along with their position.
+use super::{glob_set, Dependency, SolarConfig, WorkspaceConfig};
+use std::fmt;
+use thiserror::Error;
+
+/// Single problem found inside a solar.yaml
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// All problems found inside a solar.yaml
+#[derive(Debug, Error)]
+pub struct ConfigError {
+    pub file: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = &self.file;
+        for (i, d) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{file}:{}:{}: {}", d.line, d.column, d.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses and validates the content of a solar.yaml.
+/// `file` is only used for error messages.
+pub fn parse_config(content: &str, file: &str) -> Result<SolarConfig, ConfigError> {
+    let error = |diagnostics| ConfigError {
+        file: file.to_string(),
+        diagnostics,
+    };
+
+    // unknown fields and wrong types are reported by serde already.
+    let config: SolarConfig =
+        serde_yaml::from_str(content).map_err(|e| error(vec![serde_diagnostic(e)]))?;
+
+    let diagnostics = validate(&config, content);
+    if !diagnostics.is_empty() {
+        return Err(error(diagnostics));
+    }
+
+    Ok(config)
+}
+
+/// Parses the content of a workspace solar.yaml.
+/// `file` is only used for error messages.
+pub fn parse_workspace_config(content: &str, file: &str) -> Result<WorkspaceConfig, ConfigError> {
+    let error = |diagnostics| ConfigError {
+        file: file.to_string(),
+        diagnostics,
+    };
+
+    let config: WorkspaceConfig =
+        serde_yaml::from_str(content).map_err(|e| error(vec![serde_diagnostic(e)]))?;
+
+    if config.workspace.members.is_empty() {
+        let (line, column) = locate_key(content, "workspace", None);
+        return Err(error(vec![Diagnostic {
+            line,
+            column,
+            message: "a workspace needs at least one member".to_string(),
+        }]));
+    }
+
+    Ok(config)
+}
+
+/// Turns an error reported by serde into a diagnostic.
+fn serde_diagnostic(e: serde_yaml::Error) -> Diagnostic {
+    let mut message = e.to_string();
+    let (line, column) = match e.location() {
+        Some(l) => {
+            // the location is already part of the message.
+            let suffix = format!(" at line {} column {}", l.line(), l.column());
+            if let Some(m) = message.strip_suffix(&suffix) {
+                message = m.to_string();
+            }
+            (l.line(), l.column())
+        }
+        None => (1, 1),
+    };
+
+    Diagnostic {
+        line,
+        column,
+        message,
+    }
+}
+
+/// Checks the semantics of a config, that can't be expressed with serde.
+fn validate(config: &SolarConfig, content: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut report = |key: &str, section: Option<&str>, message: String| {
+        let (line, column) = locate_key(content, key, section);
+        diagnostics.push(Diagnostic {
+            line,
+            column,
+            message,
+        });
+    };
+
+    if let Err(e) = semver::Version::parse(&config.version) {
+        report(
+            "version",
+            None,
+            format!(
+                "version '{}' is not a valid semantic version (e.g. 0.1.0): {e}",
+                config.version
+            ),
+        );
+    }
+
+    if config.author.is_some() && config.authors.is_some() {
+        report(
+            "authors",
+            None,
+            "only one of 'author' and 'authors' may be set".to_string(),
+        );
+    }
+
+    if let Some(deps) = &config.dependencies {
+        for (key, value) in deps {
+            let dep = match Dependency::from_key_value(key, value) {
+                Ok(dep) => dep,
+                Err(message) => {
+                    report(key, Some("dependencies"), message);
+                    continue;
+                }
+            };
+
+            if dep.path.is_some() {
+                continue;
+            }
+
+            // git dependencies name a tag or commit instead.
+            if dep.repo.is_some() {
+                if dep.requirement.is_empty() {
+                    report(
+                        key,
+                        Some("dependencies"),
+                        format!("dependency '{key}' is missing a tag or commit after '@'"),
+                    );
+                }
+                continue;
+            }
+
+            if let Err(e) = semver::VersionReq::parse(&dep.requirement) {
+                report(
+                    key,
+                    Some("dependencies"),
+                    format!(
+                        "version '{}' of dependency '{key}' is not a valid version requirement (e.g. ^0.1.0): {e}",
+                        dep.requirement
+                    ),
+                );
+            }
+        }
+    }
+
+    for (key, patterns) in [("include", &config.include), ("exclude", &config.exclude)] {
+        for pattern in patterns.iter().flatten() {
+            if let Err(e) = glob_set(&[pattern]) {
+                report(key, None, format!("{e:#}"));
+            }
+        }
+    }
+
+    // report in the order of appearance
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+/// Finds the line and column (both starting at 1) of a key inside the yaml source.
+/// Without a section, only top level keys (which must not be indented) are searched.
+/// Otherwise only the keys nested inside the top level key `section` are.
+/// Falls back to the start of the file, if the key can't be found.
+fn locate_key(content: &str, key: &str, section: Option<&str>) -> (usize, usize) {
+    let mut in_section = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if indent == 0 {
+            // a new top level key ends the previous section
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                in_section = section.is_some_and(|s| trimmed.starts_with(&format!("{s}:")));
+            }
+            if section.is_some() {
+                continue;
+            }
+        } else if !in_section {
+            continue;
+        }
+
+        let unquoted = trimmed.trim_start_matches(['"', '\'']);
+        if unquoted.starts_with(key) {
+            return (index + 1, indent + 1);
+        }
+    }
+
+    (1, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(content: &str) -> Vec<(usize, usize, String)> {
+        parse_config(content, "solar.yaml")
+            .unwrap_err()
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.line, d.column, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn locates_keys_inside_their_section() {
+        let content = "name: app\nversion: 0.1.0\ndependencies:\n  version(acme): 1\n";
+
+        assert_eq!(locate_key(content, "version", None), (2, 1));
+        assert_eq!(locate_key(content, "version", Some("dependencies")), (4, 3));
+        assert_eq!(locate_key(content, "missing", Some("dependencies")), (1, 1));
+    }
+
+    #[test]
+    fn reports_invalid_dependencies_at_their_key() {
+        let content = "name: app\nversion: 0.1.0\ndependencies:\n  util(acme): ~~1\n  lib(acme): git@github.com:acme/lib\n";
+        let found = diagnostics(content);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].0, found[0].1), (4, 3));
+        assert!(found[0].2.contains("not a valid version requirement"));
+        assert_eq!((found[1].0, found[1].1), (5, 3));
+        assert!(found[1].2.contains("missing a tag or commit"));
+    }
+
+    #[test]
+    fn reports_malformed_dependency_with_its_key() {
+        let content =
+            "name: app\nversion: 0.1.0\ndependencies:\n  util(acme):\n    paht: ../util\n";
+        let found = diagnostics(content);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 5);
+        assert!(found[0].2.contains("util(acme)"), "{}", found[0].2);
+        assert!(found[0].2.contains("expected a version requirement"));
+    }
+}
@@ -0,0 +1,119 @@
+/// This file contains code
+/// for workspaces, i.e. several projects
+/// sharing one resolution of dependencies and one solar.lock.
+use super::parse_workspace_config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Structure of a solar.yaml declaring a workspace, e.g.
+///
+///     workspace:
+///       members:
+///         - app
+///         - libs/util
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    pub workspace: WorkspaceMembers,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceMembers {
+    /// Directories of the member projects, relative to the workspace root
+    pub members: Vec<String>,
+}
+
+/// A workspace found in the filesystem
+#[derive(Debug)]
+pub struct Workspace {
+    /// Directory containing the workspace solar.yaml (and the shared solar.lock)
+    pub root: String,
+    /// Root directories of all member projects, in the order they are declared
+    pub members: Vec<String>,
+}
+
+impl Workspace {
+    /// Reads the workspace declared inside `dir`.
+    /// Returns None, if the solar.yaml inside `dir` doesn't declare a workspace.
+    pub fn read(dir: &str) -> anyhow::Result<Option<Self>> {
+        let path = Path::new(dir).join("solar.yaml");
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let file = path.display().to_string();
+        let content = std::fs::read_to_string(&path).with_context(|| format!("reading {file}"))?;
+
+        // only peek at the top level keys,
+        // the solar.yaml of normal projects is validated elsewhere.
+        let is_workspace = serde_yaml::from_str::<serde_yaml::Mapping>(&content)
+            .is_ok_and(|keys| keys.contains_key("workspace"));
+        if !is_workspace {
+            return Ok(None);
+        }
+
+        let config = parse_workspace_config(&content, &file)?;
+
+        let members = config
+            .workspace
+            .members
+            .iter()
+            .map(|member| {
+                let member = format!("{dir}/{member}");
+                if !Path::new(&member).join("solar.yaml").is_file() {
+                    anyhow::bail!("workspace member {member} has no solar.yaml");
+                }
+                Ok(member)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Some(Workspace {
+            root: dir.to_string(),
+            members,
+        }))
+    }
+
+    /// Finds the workspace `dir` is a member of, by searching the parent directories.
+    /// The search stops at the first workspace found.
+    pub fn containing(dir: &str) -> anyhow::Result<Option<Self>> {
+        let dir = canonical(dir)?;
+
+        for ancestor in dir.ancestors().skip(1) {
+            let ancestor = ancestor.to_str().context("read path of directory")?;
+            let Some(workspace) = Workspace::read(ancestor)? else {
+                continue;
+            };
+
+            for member in &workspace.members {
+                if canonical(member)? == dir {
+                    return Ok(Some(workspace));
+                }
+            }
+
+            return Ok(None);
+        }
+
+        Ok(None)
+    }
+
+    /// Index of the member with root directory `dir`
+    pub fn member_index(&self, dir: &str) -> anyhow::Result<Option<usize>> {
+        let dir = canonical(dir)?;
+
+        for (i, member) in self.members.iter().enumerate() {
+            if canonical(member)? == dir {
+                return Ok(Some(i));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn canonical(dir: &str) -> anyhow::Result<PathBuf> {
+    Path::new(dir)
+        .canonicalize()
+        .with_context(|| format!("finding directory {dir}"))
+}
@@ -2,7 +2,7 @@ use hotel::HotelMap;
 use solar_parser::ast::body::BodyItem;
 
 use crate::{
-    id::{IdItem, SSID},
+    id::{IdItem, TypeId, SSID},
     project::GlobalModules,
 };
 
@@ -26,12 +26,18 @@ pub struct BuildinTypeId {
     pub float: u8,
 
     pub string: u8,
+
+    /// None, if the std library doesn't declare `buildin_type Array`
+    pub array: Option<u8>,
+    /// Array containing Strings, e.g. the arguments passed to a program
+    pub string_array: Option<u8>,
 }
 
 // Only the stdlibary is allowed to declare buildin types!
 pub fn link_buildin_types(modules: &GlobalModules) -> (HotelMap<SSID, Type>, BuildinTypeId) {
     let mut tys = HotelMap::new();
     let mut ids = BuildinTypeId::default();
+    let mut array_symbol = None;
 
     // Find std library
     let stdpaths = modules
@@ -59,7 +65,7 @@ pub fn link_buildin_types(modules: &GlobalModules) -> (HotelMap<SSID, Type>, Bui
                         size_in_bytes: 0,
                     };
 
-                    let id = tys.insert(ssid, ty) as u8;
+                    let id = tys.insert(ssid.clone(), ty) as u8;
 
                     match item.name.value {
                         "Bool" => ids.bool = id,
@@ -74,6 +80,10 @@ pub fn link_buildin_types(modules: &GlobalModules) -> (HotelMap<SSID, Type>, Bui
                         "Float32" => ids.float32 = id,
                         "Float" => ids.float = id,
                         "String" => ids.string = id,
+                        "Array" => {
+                            ids.array = Some(id);
+                            array_symbol = Some(ssid.0);
+                        }
                         x => panic!("unrecognized buildin: {x}"),
                     }
                 }
@@ -81,5 +91,19 @@ pub fn link_buildin_types(modules: &GlobalModules) -> (HotelMap<SSID, Type>, Bui
         }
     }
 
+    // Arrays are generic over their elements,
+    // so every kind of array gets its own static type.
+    if let Some(array_symbol) = array_symbol {
+        let ssid: SSID = (array_symbol.clone(), vec![ids.string as TypeId]);
+        let ty = Type {
+            info_name: "Array String".to_string(),
+            module: array_symbol.0,
+            field_layout: Vec::new(),
+            size_in_bytes: 0,
+        };
+
+        ids.string_array = Some(tys.insert(ssid, ty) as u8);
+    }
+
     (tys, ids)
 }